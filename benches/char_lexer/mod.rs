@@ -0,0 +1,686 @@
+// The Vec<char> lexer as it was before the zero-copy rewrite in src/lexer,
+// kept only as a throughput baseline for benches/lexer_throughput.rs.
+//
+// This file is intentionally frozen. Apart from this header and the `allow`
+// below it, it is a verbatim copy of `git show 9748329:src/lexer/mod.rs`,
+// and it must stay that way: do not port language changes, fixes or lints
+// to it, or the bench stops measuring the rewrite. It only has to lex the
+// bench's SNIPPET, which the bench checks by comparing token counts with
+// the current lexer.
+#![allow(dead_code, clippy::upper_case_acronyms)]
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Token {
+    Illegal,
+    EOF,
+
+    // Identifiers + literals
+    Ident(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+
+    // "total: ${a + b}" lexes as
+    // TemplateStart, StringFragment("total: "), InterpolationStart,
+    // a, +, b, InterpolationEnd, TemplateEnd
+    TemplateStart,
+    StringFragment(String),
+    InterpolationStart,
+    InterpolationEnd,
+    TemplateEnd,
+
+    // Keywords
+    Let,
+    Fn,
+    Return,
+    True,
+    False,
+    If,
+    Else,
+
+    // Operators
+    Assign,
+    Plus,
+    Minus,
+    Asterisk,
+    Slash,
+    Bang,
+    LessThan,
+    GreaterThan,
+    Equal,
+    NotEqual,
+    LessEqual,
+    GreaterEqual,
+    Percent,
+    Power,
+    Ampersand,
+    Pipe,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+
+
+    // Delimiters
+    Comma,
+    Semicolon,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+}
+
+// Where a token came from in the source text.
+// start/end are byte offsets, line/column are 1-based and point at the first char.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+// A token together with the span it was read from.
+// `docs` holds the `///` doc comments written directly before the token,
+// one entry per line, so tooling can recover them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+    pub docs: Vec<String>,
+}
+
+// Lets callers compare against a bare Token: `tok == Token::Semicolon`.
+impl PartialEq<Token> for SpannedToken {
+    fn eq(&self, other: &Token) -> bool {
+        self.token == *other
+    }
+}
+
+// A problem found while lexing, e.g. an unterminated string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+// What the lexer is inside of. Interpolation re-enters normal lexing, so
+// strings and interpolations can nest: "a ${ "b ${c}" } d".
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    // Between the quotes of an interpolated string; `open` is the opening quote.
+    Template { open: Span },
+    // Inside `${ ... }`; `depth` counts unclosed '{' so the right '}' ends it.
+    Interpolation { depth: usize },
+}
+
+pub struct Lexer {
+    input: Vec<char>,
+    position: usize,      
+    read_position: usize, 
+    ch: char,             
+    offset: usize,        // byte offset of ch
+    line: usize,          // line of ch (1-based)
+    column: usize,        // column of ch (1-based)
+    errors: Vec<LexError>,
+    modes: Vec<Mode>,
+}
+
+impl Lexer {
+    pub fn new(input: &str) -> Self {
+        let mut l = Lexer {
+            input: input.chars().collect(),
+            position: 0,
+            read_position: 0,
+            ch: '\0',
+            offset: 0,
+            line: 1,
+            column: 0,
+            errors: vec![],
+            modes: vec![],
+        };
+        l.read_char();  
+        l
+    }
+
+    fn read_char(&mut self) {
+        // Step the location past the char we are leaving behind.
+        if self.read_position == 0 {
+            self.column = 1;
+        } else if self.position < self.input.len() {
+            self.offset += self.ch.len_utf8();
+            if self.ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+
+        if self.read_position >= self.input.len() {
+            self.ch = '\0';
+        } else {
+            self.ch = self.input[self.read_position];
+        }
+        self.position = self.read_position;
+        self.read_position += 1;
+    }
+
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    fn error(&mut self, message: impl Into<String>, span: Span) {
+        self.errors.push(LexError { message: message.into(), span });
+    }
+
+    // Zero-width span at the current char; callers fill in `end`.
+    fn location(&self) -> Span {
+        Span { start: self.offset, end: self.offset, line: self.line, column: self.column }
+    }
+
+    fn at_eof(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    // Skips whitespace and comments, returning any doc comments passed on the way.
+    fn skip_trivia(&mut self) -> Vec<String> {
+        let mut docs = vec![];
+
+        loop {
+            while self.ch.is_whitespace() {
+                self.read_char();
+            }
+
+            match (self.ch, self.peek_char()) {
+                ('/', '/') => {
+                    // `///` is a doc comment, but `////...` is an ordinary one.
+                    let is_doc = self.peek_char_at(2) == '/' && self.peek_char_at(3) != '/';
+                    let text = self.read_line_comment();
+                    if is_doc {
+                        let text = &text[3..];
+                        docs.push(text.strip_prefix(' ').unwrap_or(text).to_string());
+                    }
+                }
+                ('/', '*') => self.skip_block_comment(),
+                _ => return docs,
+            }
+        }
+    }
+
+    fn read_line_comment(&mut self) -> String {
+        let start = self.position;
+        while !self.at_eof() && self.ch != '\n' {
+            self.read_char();
+        }
+        self.input[start..self.position].iter().collect()
+    }
+
+    // Block comments nest: `/* a /* b */ c */` is one comment.
+    fn skip_block_comment(&mut self) {
+        let open = self.location();
+        let mut depth = 0;
+
+        loop {
+            if self.at_eof() {
+                self.error("unterminated block comment", Span { end: self.offset, ..open });
+                return;
+            }
+            match (self.ch, self.peek_char()) {
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    self.read_char();
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => self.read_char(),
+            }
+        }
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let start = self.position;
+        while self.ch.is_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
+        self.input[start..self.position].iter().collect()
+    }
+
+    // Reads an integer or float literal, checking its value as it goes.
+    // Accepts 0x/0b/0o prefixes, `_` separators, fractions and exponents.
+    // A malformed or out-of-range literal is reported and lexes as Illegal.
+    fn read_number(&mut self) -> Token {
+        let start = self.location();
+
+        let radix = match (self.ch, self.peek_char()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'b' | 'B') => 2,
+            ('0', 'o' | 'O') => 8,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.read_char();
+            self.read_char();
+            let digits = self.read_digits(radix);
+            return self.finish_integer(&digits, radix, start);
+        }
+
+        let mut text = self.read_digits(10);
+        let mut is_float = false;
+
+        // Only a digit after '.' makes a fraction, so `1.x` and `1..2` still lex.
+        if self.ch == '.' && self.peek_char().is_ascii_digit() {
+            is_float = true;
+            text.push('.');
+            self.read_char();
+            text.push_str(&self.read_digits(10));
+        }
+
+        if matches!(self.ch, 'e' | 'E') {
+            let sign = self.peek_char();
+            let digit_at = if matches!(sign, '+' | '-') { 2 } else { 1 };
+            if self.peek_char_at(digit_at).is_ascii_digit() {
+                is_float = true;
+                text.push('e');
+                self.read_char();
+                if digit_at == 2 {
+                    text.push(sign);
+                    self.read_char();
+                }
+                text.push_str(&self.read_digits(10));
+            }
+        }
+
+        if !is_float {
+            return self.finish_integer(&text, 10, start);
+        }
+
+        if !self.check_number_end(start) {
+            return Token::Illegal;
+        }
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Token::Float(value),
+            _ => {
+                self.error("float literal out of range", Span { end: self.offset, ..start });
+                Token::Illegal
+            }
+        }
+    }
+
+    // Reads alphanumeric chars and `_` separators; returns just the digits.
+    // Anything that is not a digit of `radix` is kept so the caller can report it.
+    fn read_digits(&mut self, radix: u32) -> String {
+        let start = self.location();
+        let mut digits = String::new();
+        let mut last_was_separator = false;
+
+        while self.ch == '_' || self.ch.is_digit(radix) {
+            if self.ch == '_' {
+                if digits.is_empty() || last_was_separator {
+                    let here = self.location();
+                    self.error("misplaced digit separator '_'", Span { end: here.start + 1, ..here });
+                }
+                last_was_separator = true;
+            } else {
+                digits.push(self.ch);
+                last_was_separator = false;
+            }
+            self.read_char();
+        }
+
+        if last_was_separator {
+            self.error("trailing digit separator '_'", Span { end: self.offset, ..start });
+        }
+        digits
+    }
+
+    fn finish_integer(&mut self, digits: &str, radix: u32, start: Span) -> Token {
+        if !self.check_number_end(start) {
+            return Token::Illegal;
+        }
+
+        let span = Span { end: self.offset, ..start };
+        if digits.is_empty() {
+            self.error("missing digits after number prefix", span);
+            return Token::Illegal;
+        }
+        match i64::from_str_radix(digits, radix) {
+            Ok(value) => Token::Int(value),
+            Err(_) => {
+                self.error("integer literal out of range", span);
+                Token::Illegal
+            }
+        }
+    }
+
+    // A number must not run straight into letters or other digits: `12ab`, `0b102`.
+    fn check_number_end(&mut self, start: Span) -> bool {
+        if !(self.ch.is_alphanumeric() || self.ch == '_') {
+            return true;
+        }
+
+        let bad = self.ch;
+        while self.ch.is_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
+        self.error(
+            format!("invalid character '{}' in number literal", bad),
+            Span { end: self.offset, ..start },
+        );
+        false
+    }
+
+    fn read_string(&mut self) -> Token {
+        let open = self.location();
+        self.read_char(); // skip opening '"'
+
+        if self.has_interpolation() {
+            self.modes.push(Mode::Template { open });
+            return Token::TemplateStart;
+        }
+
+        let value = self.read_string_contents(false);
+        if self.at_eof() {
+            self.error("unterminated string literal", Span { end: self.offset, ..open });
+        } else {
+            self.read_char(); // skip closing '"'
+        }
+        Token::String(value)
+    }
+
+    // Looks ahead from just after an opening quote for an unescaped `${`
+    // before the closing quote.
+    fn has_interpolation(&self) -> bool {
+        let mut i = self.position;
+        while let Some(&c) = self.input.get(i) {
+            match c {
+                '"' => return false,
+                '\\' => i += 2,
+                '$' if self.input.get(i + 1) == Some(&'{') => return true,
+                _ => i += 1,
+            }
+        }
+        false
+    }
+
+    // Reads string text up to the closing quote or EOF, resolving escapes.
+    // In a template it also stops in front of `${`.
+    fn read_string_contents(&mut self, in_template: bool) -> String {
+        let mut value = String::new();
+        while !self.at_eof() && self.ch != '"' {
+            match self.ch {
+                '$' if in_template && self.peek_char() == '{' => break,
+                '\\' => {
+                    if let Some(c) = self.read_escape() {
+                        value.push(c);
+                    }
+                }
+                c => {
+                    value.push(c);
+                    self.read_char();
+                }
+            }
+        }
+        value
+    }
+
+    // The next piece of an interpolated string: text, `${`, or the closing quote.
+    fn read_template_part(&mut self, open: Span) -> Token {
+        if self.at_eof() {
+            self.modes.pop();
+            self.error("unterminated string literal", Span { end: self.offset, ..open });
+            return Token::TemplateEnd;
+        }
+
+        match self.ch {
+            '"' => {
+                self.read_char();
+                self.modes.pop();
+                Token::TemplateEnd
+            }
+            '$' if self.peek_char() == '{' => {
+                self.read_char();
+                self.read_char();
+                self.modes.push(Mode::Interpolation { depth: 0 });
+                Token::InterpolationStart
+            }
+            _ => Token::StringFragment(self.read_string_contents(true)),
+        }
+    }
+
+    // Reads one escape sequence starting at '\'. Bad escapes are reported and skipped.
+    fn read_escape(&mut self) -> Option<char> {
+        let start = self.location();
+        self.read_char(); // skip '\'
+
+        if self.at_eof() {
+            return None; // read_string reports the unterminated literal
+        }
+
+        let c = match self.ch {
+            'n' => '\n',
+            't' => '\t',
+            '"' => '"',
+            '\\' => '\\',
+            '$' => '$',
+            'u' => return self.read_unicode_escape(start),
+            other => {
+                self.read_char();
+                self.error(
+                    format!("unknown escape sequence '\\{}'", other),
+                    Span { end: self.offset, ..start },
+                );
+                return None;
+            }
+        };
+        self.read_char();
+        Some(c)
+    }
+
+    // \u{1F600}: one to six hex digits naming a Unicode scalar value.
+    fn read_unicode_escape(&mut self, start: Span) -> Option<char> {
+        self.read_char(); // skip 'u'
+
+        if self.ch != '{' {
+            self.error("expected '{' after '\\u'", Span { end: self.offset, ..start });
+            return None;
+        }
+        self.read_char();
+
+        let mut digits = String::new();
+        while self.ch.is_ascii_hexdigit() {
+            digits.push(self.ch);
+            self.read_char();
+        }
+
+        if self.ch != '}' {
+            self.error("unterminated unicode escape", Span { end: self.offset, ..start });
+            return None;
+        }
+        self.read_char();
+
+        let span = Span { end: self.offset, ..start };
+        if digits.is_empty() || digits.len() > 6 {
+            self.error("unicode escape must have 1 to 6 hex digits", span);
+            return None;
+        }
+        match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+            Some(c) => Some(c),
+            None => {
+                self.error(format!("invalid unicode code point '{}'", digits), span);
+                None
+            }
+        }
+    }
+
+    pub fn next_token(&mut self) -> SpannedToken {
+        // Inside the quotes of a template, whitespace and `//` are string text.
+        if let Some(&Mode::Template { open }) = self.modes.last() {
+            let start = self.location();
+            let token = self.read_template_part(open);
+            return SpannedToken {
+                token,
+                span: Span { end: self.offset, ..start },
+                docs: vec![],
+            };
+        }
+
+        let docs = self.skip_trivia();
+
+        let (start, line, column) = (self.offset, self.line, self.column);
+        let token = self.read_token();
+
+        SpannedToken {
+            token,
+            span: Span { start, end: self.offset, line, column },
+            docs,
+        }
+    }
+
+    // Reads a one-char operator, or a two-char one when the next char
+    // matches an entry in `longer`, e.g. '<' followed by '=' is LessEqual.
+    fn read_operator(&mut self, single: Token, longer: &[(char, Token)]) -> Token {
+        let next = self.peek_char();
+        self.read_char();
+        for (ch, token) in longer {
+            if *ch == next {
+                self.read_char();
+                return token.clone();
+            }
+        }
+        single
+    }
+
+    fn read_token(&mut self) -> Token {
+        let tok = match self.ch {
+            '=' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    self.read_char();
+                    Token::Equal
+                } else {
+                    self.read_char();
+                    Token::Assign
+                }
+            }
+            '+' => self.read_operator(Token::Plus, &[('=', Token::PlusAssign)]),
+            '-' => self.read_operator(Token::Minus, &[('=', Token::MinusAssign)]),
+            '*' => self.read_operator(Token::Asterisk, &[('*', Token::Power), ('=', Token::AsteriskAssign)]),
+            '/' => self.read_operator(Token::Slash, &[('=', Token::SlashAssign)]),
+            '%' => { self.read_char(); Token::Percent }
+            '!' => {
+                if self.peek_char() == '=' {
+                    self.read_char();
+                    self.read_char();
+                    Token::NotEqual
+                } else {
+                    self.read_char();
+                    Token::Bang
+                }
+            }
+            '<' => self.read_operator(Token::LessThan, &[('=', Token::LessEqual), ('<', Token::ShiftLeft)]),
+            '>' => self.read_operator(Token::GreaterThan, &[('=', Token::GreaterEqual), ('>', Token::ShiftRight)]),
+            '&' => self.read_operator(Token::Ampersand, &[('&', Token::And)]),
+            '|' => self.read_operator(Token::Pipe, &[('|', Token::Or)]),
+            '^' => { self.read_char(); Token::Caret }
+            '(' => { self.read_char(); Token::LParen }
+            ')' => { self.read_char(); Token::RParen }
+            '{' => {
+                self.read_char();
+                if let Some(Mode::Interpolation { depth }) = self.modes.last_mut() {
+                    *depth += 1;
+                }
+                Token::LBrace
+            }
+            '}' => {
+                self.read_char();
+                match self.modes.last_mut() {
+                    Some(Mode::Interpolation { depth: 0 }) => {
+                        self.modes.pop();
+                        Token::InterpolationEnd
+                    }
+                    Some(Mode::Interpolation { depth }) => {
+                        *depth -= 1;
+                        Token::RBrace
+                    }
+                    _ => Token::RBrace,
+                }
+            }
+            ',' => { self.read_char(); Token::Comma }
+            ';' => { self.read_char(); Token::Semicolon }
+            '"' => self.read_string(),
+            '\0' => {
+                // EOF inside `${` means the enclosing string never closed either.
+                if let Some(open) = self.modes.iter().rev().find_map(|mode| match mode {
+                    Mode::Template { open } => Some(*open),
+                    _ => None,
+                }) {
+                    self.modes.clear();
+                    self.error("unterminated string interpolation", Span { end: self.offset, ..open });
+                }
+                Token::EOF
+            }
+
+            _ => {
+                if self.ch.is_alphabetic() {
+                    let ident = self.read_identifier();
+                    return match ident.as_str() {
+                        "let" => Token::Let,
+                        "fn" => Token::Fn,
+                        "return" => Token::Return,
+                        "true" => Token::True,
+                        "false" => Token::False,
+                        "if" => Token::If,
+                        "else" => Token::Else,
+                        _ => Token::Ident(ident),
+                    };
+                } else if self.ch.is_ascii_digit() {
+                    return self.read_number();
+                } else {
+                    Token::Illegal
+                }
+            }
+        };
+
+        tok
+    }
+    fn peek_char(&self) -> char {
+        if self.read_position >= self.input.len() {
+            '\0'
+        } else {
+            self.input[self.read_position]
+        }
+    }
+
+    // peek_char_at(1) is the same as peek_char().
+    fn peek_char_at(&self, n: usize) -> char {
+        self.input.get(self.position + n).copied().unwrap_or('\0')
+    }
+
+}
@@ -0,0 +1,63 @@
+// Lexer throughput: the zero-copy lexer against the old Vec<char> one.
+// Run with: cargo bench --bench lexer_throughput
+
+mod char_lexer;
+
+use aion::lexer::Lexer;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SNIPPET: &str = r#"
+/// Running totals for a generated report.
+let total_count = 0x1F + 0b1010 * 1_000;
+let ratio = 3.25e-2 / (total_count - 7);
+let label = "item \"${total_count}\" done\n";
+/* block comment /* nested */ */
+let ok = total_count >= 10 && ratio < 1.0 || !(total_count == 3);
+total_count += 1; // trailing comment
+"#;
+
+const REPEAT: usize = 20_000;
+const RUNS: usize = 10;
+
+fn main() {
+    let source = SNIPPET.repeat(REPEAT);
+    let megabytes = source.len() as f64 / (1024.0 * 1024.0);
+
+    let (old_time, old_tokens) = best_of(|| {
+        let mut lexer = char_lexer::Lexer::new(&source);
+        let mut count = 0;
+        while lexer.next_token() != char_lexer::Token::EOF {
+            count += 1;
+        }
+        count
+    });
+    let (new_time, new_tokens) = best_of(|| Lexer::new(&source).count());
+
+    assert_eq!(old_tokens, new_tokens, "both lexers should see the same tokens");
+
+    println!("input: {:.1} MiB, {} tokens, best of {} runs", megabytes, new_tokens, RUNS);
+    report("Vec<char> lexer", old_time, megabytes);
+    report("zero-copy lexer", new_time, megabytes);
+    println!("speedup: {:.2}x", old_time.as_secs_f64() / new_time.as_secs_f64());
+}
+
+fn best_of(mut lex: impl FnMut() -> usize) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        tokens = black_box(lex());
+        best = best.min(start.elapsed());
+    }
+    (best, tokens)
+}
+
+fn report(name: &str, time: Duration, megabytes: f64) {
+    println!(
+        "{:<16} {:>8.2} ms  {:>8.1} MiB/s",
+        name,
+        time.as_secs_f64() * 1000.0,
+        megabytes / time.as_secs_f64()
+    );
+}
@@ -1,32 +1,354 @@
-use crate::lexer::Token;
+use crate::lexer::Span;
+use std::fmt;
+
+// The statements between `{` and `}`.
+pub type Block = Vec<Statement>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
+    // let x = 5; or let x: int = 5; `span` points at the `let` (or the `fn`
+    // of `fn name() { ... }`) for runtime errors.
     LetStatement {
         name: String,
+        annotation: Option<TypeAnnotation>,
+        value: Expression,
+        span: Span,
+    },
+    // let [a, b] = pair; `span` points at the `let` for runtime errors.
+    LetPattern {
+        pattern: Pattern,
         value: Expression,
+        span: Span,
+    },
+    // Like `let`, but the name can never be assigned to again, nor declared
+    // again in the same scope.
+    ConstStatement {
+        name: String,
+        annotation: Option<TypeAnnotation>,
+        value: Expression,
+        span: Span,
     },
     ReturnStatement {
         value: Expression,
     },
+    // throw "message"; or throw err; `span` points at the `throw`.
+    Throw {
+        value: Expression,
+        span: Span,
+    },
     ExpressionStatement(Expression),
+    Block(Block),
+    // struct Point { x, y }
+    StructDeclaration {
+        name: String,
+        fields: Vec<String>,
+    },
+    // enum Shape { Circle(r), Rect(w, h), Empty }
+    EnumDeclaration {
+        name: String,
+        variants: Vec<Variant>,
+    },
+    // impl Point { fn norm(self) { ... } } or impl Show for Point { ... };
+    // `span` points at the `impl`.
+    Impl {
+        type_name: String,
+        trait_name: Option<String>,
+        methods: Vec<Method>,
+        span: Span,
+    },
+    // trait Show { fn show(self); fn print(self) { ... } }; `span` points
+    // at the `trait`.
+    Trait {
+        name: String,
+        methods: Vec<TraitMethod>,
+        span: Span,
+    },
+    // import "lib/math.aion" as math; `span` points at the `import`.
+    Import {
+        path: String,
+        alias: String,
+        span: Span,
+    },
+    // export let x = 1; export struct Point { x, y }
+    Export(Box<Statement>),
+    // `break` or `break value`; only valid inside a loop.
+    Break {
+        value: Option<Expression>,
+    },
+    Continue,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
-    Identifier(String),
+    // `span` points at the name for runtime errors.
+    Identifier {
+        name: String,
+        span: Span,
+    },
     NumberLiteral(i64),
+    FloatLiteral(f64),
+    StringLiteral(String),
+    BooleanLiteral(bool),
+    NullLiteral,
+    // "total: ${a + b}"
+    Interpolated(Vec<InterpolationPart>),
+    // [1, 2, 3]
+    ArrayLiteral(Vec<Expression>),
+    // {"name": "x", 1: true}, keeping the pairs in source order. Each pair's
+    // span points at its key for runtime errors.
+    HashLiteral(Vec<(Expression, Expression, Span)>),
 
+    // -x, !x; `span` points at the operator for errors.
     Prefix {
         operator: String,
         right: Box<Expression>,
+        span: Span,
     },
 
+    // a + b; `span` points at the operator for errors.
     Infix {
         left: Box<Expression>,
         operator: String,
         right: Box<Expression>,
+        span: Span,
+    },
+
+    // x = 1, x += 1; `span` points at the operator for runtime errors.
+    Assign {
+        target: Box<Expression>,
+        operator: String,
+        value: Box<Expression>,
+        span: Span,
+    },
+
+    // fn(a, b) { a + b } or fn<T: Show>(a: T, b: int) -> int { b }; each
+    // parameter has an entry in `parameter_types`.
+    FunctionLiteral {
+        type_parameters: Vec<TypeParameter>,
+        parameters: Vec<String>,
+        parameter_types: Vec<Option<TypeAnnotation>>,
+        return_type: Option<TypeAnnotation>,
+        body: Block,
     },
+
+    // add(1, 2); `span` points at the '(' for runtime errors.
+    Call {
+        function: Box<Expression>,
+        arguments: Vec<Expression>,
+        span: Span,
+    },
+
+    // Point { x: 1, y }; `y` alone is short for `y: y`. `span` points at
+    // the type name for runtime errors.
+    StructLiteral {
+        name: String,
+        fields: Vec<(String, Expression)>,
+        span: Span,
+    },
+
+    // Shape::Circle, Point::origin; `span` points at the '::'.
+    Path {
+        object: Box<Expression>,
+        member: String,
+        span: Span,
+    },
+
+    // p.x; `span` points at the '.' for runtime errors.
+    Field {
+        object: Box<Expression>,
+        field: String,
+        span: Span,
+    },
+
+    // a[i]; `span` points at the '[' for runtime errors.
+    Index {
+        left: Box<Expression>,
+        index: Box<Expression>,
+        span: Span,
+    },
+
+    // a[start:end], where either bound may be left out.
+    Slice {
+        left: Box<Expression>,
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+        span: Span,
+    },
+
+    // if cond { ... } else { ... }; `else if` nests another If in `alternative`.
+    If {
+        condition: Box<Expression>,
+        consequence: Block,
+        alternative: Option<Block>,
+    },
+
+    // match value { pattern [if guard] => body, ... }; `span` points at the
+    // `match` for runtime errors.
+    Match {
+        subject: Box<Expression>,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
+
+    // while cond { ... }
+    While {
+        condition: Box<Expression>,
+        body: Block,
+    },
+
+    // loop { ... }; its value is whatever `break` hands out.
+    Loop {
+        body: Block,
+    },
+
+    // for x in iterable { ... }; `span` points at the `for` for runtime errors.
+    For {
+        variable: String,
+        iterable: Box<Expression>,
+        body: Block,
+        span: Span,
+    },
+
+    // try { ... } catch err { ... } finally { ... }; at least one of the
+    // catch and finally blocks is present.
+    Try {
+        body: Block,
+        catch: Option<Catch>,
+        finally: Option<Block>,
+    },
+}
+
+// `catch err { ... }`, or `catch { ... }` when the error is not needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Catch {
+    pub name: Option<String>,
+    pub body: Block,
+}
+
+// One variant of an enum, e.g. `Rect(w, h)`; `fields` is empty for a
+// variant without a payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+// fn name(self, ...) { ... } inside an impl block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method {
+    pub name: String,
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<String>,
+    pub parameter_types: Vec<Option<TypeAnnotation>>,
+    pub return_type: Option<TypeAnnotation>,
+    pub body: Block,
+}
+
+// fn name(self, ...); inside a trait, or with a body that impls get when
+// they leave the method out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitMethod {
+    pub name: String,
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<String>,
+    pub parameter_types: Vec<Option<TypeAnnotation>>,
+    pub return_type: Option<TypeAnnotation>,
+    pub default: Option<Block>,
+}
+
+// `T: Show + Eq` in fn name<T: Show + Eq>(...); `span` points at the name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeParameter {
+    pub name: String,
+    pub bounds: Vec<String>,
+    pub span: Span,
+}
+
+// A type written after `:` or `->`; `span` points at where it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAnnotation {
+    pub ty: TypeExpr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExpr {
+    // int, string, any, Point, ...
+    Named(String),
+    // [int]
+    Array(Box<TypeExpr>),
+    // {string: int}
+    Hash(Box<TypeExpr>, Box<TypeExpr>),
+    // fn(int, int) -> int
+    Function(Vec<TypeExpr>, Box<TypeExpr>),
+}
+
+impl fmt::Display for TypeParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.bounds.is_empty() {
+            write!(f, ": {}", self.bounds.join(" + "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeExpr::Named(name) => write!(f, "{}", name),
+            TypeExpr::Array(element) => write!(f, "[{}]", element),
+            TypeExpr::Hash(key, value) => write!(f, "{{{}: {}}}", key, value),
+            TypeExpr::Function(parameters, ret) => {
+                write!(f, "fn(")?;
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", parameter)?;
+                }
+                write!(f, ") -> {}", ret)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: Block,
+}
+
+// The left side of a match arm or of `let [a, b] = ...`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // _
+    Wildcard,
+    // A name, which matches anything and binds it.
+    Binding(String),
+    // 0, -1.5, "text", true, null
+    Literal(Expression),
+    // [first, ..rest]; at most one Rest, anywhere in the list.
+    Array(Vec<Pattern>),
+    // `..rest` or a bare `..`, soaking up the elements the others leave.
+    Rest(Option<String>),
+    // {"key": pattern}; the hash may hold other keys too.
+    Hash(Vec<(Expression, Pattern)>),
+    // Shape::Rect(w, h), Some(x), Shape::Empty. `enum_name` is None for a
+    // bare variant name; `span` points at the start for runtime errors.
+    Variant {
+        enum_name: Option<String>,
+        variant: String,
+        fields: Vec<Pattern>,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    Literal(String),
+    Expr(Expression),
 }
 
 impl Expression {
@@ -34,7 +356,7 @@ impl Expression {
         let pad = " ".repeat(indent);
 
         match self {
-            Expression::Identifier(name) => {
+            Expression::Identifier { name, .. } => {
                 println!("{}{{ \"type\": \"Identifier\", \"name\": \"{}\" }}", pad, name);
             }
 
@@ -42,14 +364,58 @@ impl Expression {
                 println!("{}{{ \"type\": \"NumberLiteral\", \"value\": {} }}", pad, value);
             }
 
-            Expression::Prefix { operator, right } => {
+            Expression::FloatLiteral(value) => {
+                println!("{}{{ \"type\": \"FloatLiteral\", \"value\": {:?} }}", pad, value);
+            }
+
+            Expression::StringLiteral(value) => {
+                println!("{}{{ \"type\": \"StringLiteral\", \"value\": {:?} }}", pad, value);
+            }
+
+            Expression::BooleanLiteral(value) => {
+                println!("{}{{ \"type\": \"BooleanLiteral\", \"value\": {} }}", pad, value);
+            }
+
+            Expression::NullLiteral => {
+                println!("{}{{ \"type\": \"NullLiteral\" }}", pad);
+            }
+
+            Expression::Interpolated(parts) => {
+                println!("{}{{ \"type\": \"Interpolated\", \"parts\": [", pad);
+                for part in parts {
+                    match part {
+                        InterpolationPart::Literal(text) => println!("{}    {:?}", pad, text),
+                        InterpolationPart::Expr(expr) => expr.print(indent + 4),
+                    }
+                }
+                println!("{}]}}", pad);
+            }
+
+            Expression::ArrayLiteral(elements) => {
+                println!("{}{{ \"type\": \"ArrayLiteral\", \"elements\": [", pad);
+                for element in elements {
+                    element.print(indent + 4);
+                }
+                println!("{}]}}", pad);
+            }
+
+            Expression::HashLiteral(pairs) => {
+                println!("{}{{ \"type\": \"HashLiteral\", \"pairs\": [", pad);
+                for (key, value, _) in pairs {
+                    key.print(indent + 4);
+                    value.print(indent + 4);
+                }
+                println!("{}]}}", pad);
+            }
+
+            Expression::Prefix { operator, right, .. } => {
                 println!("{}{{ \"type\": \"Prefix\", \"operator\": \"{}\",", pad, operator);
                 println!("{}  \"right\":", pad);
                 right.print(indent + 4);
                 println!("{}}}", pad);
             }
 
-            Expression::Infix { left, operator, right } => {
+            Expression::Infix { left, operator, right, .. } => {
                 println!("{}{{ \"type\": \"Infix\", \"operator\": \"{}\",", pad, operator);
                 println!("{}  \"left\":", pad);
                 left.print(indent + 4);
@@ -57,6 +423,222 @@ impl Expression {
                 right.print(indent + 4);
                 println!("{}}}", pad);
             }
+
+            Expression::FunctionLiteral { type_parameters, parameters, parameter_types, return_type, body } => {
+                println!("{}{{ \"type\": \"FunctionLiteral\", \"parameters\": {:?},", pad, parameters);
+                if !type_parameters.is_empty() {
+                    let names: Vec<String> = type_parameters.iter().map(|parameter| parameter.to_string()).collect();
+                    println!("{}  \"type_parameters\": {:?},", pad, names);
+                }
+                if parameter_types.iter().any(Option::is_some) {
+                    let types: Vec<String> = parameter_types.iter().map(|ty| annotation_name(ty.as_ref())).collect();
+                    println!("{}  \"parameter_types\": {:?},", pad, types);
+                }
+                if let Some(return_type) = return_type {
+                    println!("{}  \"return_type\": \"{}\",", pad, return_type.ty);
+                }
+                println!("{}  \"body\":", pad);
+                print_block(body, indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::Call { function, arguments, .. } => {
+                println!("{}{{ \"type\": \"Call\",", pad);
+                println!("{}  \"function\":", pad);
+                function.print(indent + 4);
+                println!("{}  \"arguments\": [", pad);
+                for arg in arguments {
+                    arg.print(indent + 4);
+                }
+                println!("{}  ]", pad);
+                println!("{}}}", pad);
+            }
+
+            Expression::StructLiteral { name, fields, .. } => {
+                println!("{}{{ \"type\": \"StructLiteral\", \"name\": \"{}\", \"fields\": [", pad, name);
+                for (field, value) in fields {
+                    println!("{}    \"{}\":", pad, field);
+                    value.print(indent + 4);
+                }
+                println!("{}]}}", pad);
+            }
+
+            Expression::Path { object, member, .. } => {
+                println!("{}{{ \"type\": \"Path\", \"member\": \"{}\",", pad, member);
+                println!("{}  \"object\":", pad);
+                object.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::Field { object, field, .. } => {
+                println!("{}{{ \"type\": \"Field\", \"field\": \"{}\",", pad, field);
+                println!("{}  \"object\":", pad);
+                object.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::Index { left, index, .. } => {
+                println!("{}{{ \"type\": \"Index\",", pad);
+                println!("{}  \"left\":", pad);
+                left.print(indent + 4);
+                println!("{}  \"index\":", pad);
+                index.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::Slice { left, start, end, .. } => {
+                println!("{}{{ \"type\": \"Slice\",", pad);
+                println!("{}  \"left\":", pad);
+                left.print(indent + 4);
+                if let Some(start) = start {
+                    println!("{}  \"start\":", pad);
+                    start.print(indent + 4);
+                }
+                if let Some(end) = end {
+                    println!("{}  \"end\":", pad);
+                    end.print(indent + 4);
+                }
+                println!("{}}}", pad);
+            }
+
+            Expression::If { condition, consequence, alternative } => {
+                println!("{}{{ \"type\": \"If\",", pad);
+                println!("{}  \"condition\":", pad);
+                condition.print(indent + 4);
+                println!("{}  \"consequence\":", pad);
+                print_block(consequence, indent + 4);
+                if let Some(alternative) = alternative {
+                    println!("{}  \"alternative\":", pad);
+                    print_block(alternative, indent + 4);
+                }
+                println!("{}}}", pad);
+            }
+
+            Expression::Match { subject, arms, .. } => {
+                println!("{}{{ \"type\": \"Match\",", pad);
+                println!("{}  \"subject\":", pad);
+                subject.print(indent + 4);
+                println!("{}  \"arms\": [", pad);
+                for arm in arms {
+                    println!("{}    {{ \"pattern\":", pad);
+                    arm.pattern.print(indent + 8);
+                    if let Some(guard) = &arm.guard {
+                        println!("{}      \"guard\":", pad);
+                        guard.print(indent + 8);
+                    }
+                    println!("{}      \"body\":", pad);
+                    print_block(&arm.body, indent + 8);
+                    println!("{}    }}", pad);
+                }
+                println!("{}  ]", pad);
+                println!("{}}}", pad);
+            }
+
+            Expression::Try { body, catch, finally } => {
+                println!("{}{{ \"type\": \"Try\",", pad);
+                println!("{}  \"body\":", pad);
+                print_block(body, indent + 4);
+                if let Some(catch) = catch {
+                    match &catch.name {
+                        Some(name) => println!("{}  \"catch\": \"{}\",", pad, name),
+                        None => println!("{}  \"catch\": null,", pad),
+                    }
+                    print_block(&catch.body, indent + 4);
+                }
+                if let Some(finally) = finally {
+                    println!("{}  \"finally\":", pad);
+                    print_block(finally, indent + 4);
+                }
+                println!("{}}}", pad);
+            }
+
+            Expression::While { condition, body } => {
+                println!("{}{{ \"type\": \"While\",", pad);
+                println!("{}  \"condition\":", pad);
+                condition.print(indent + 4);
+                println!("{}  \"body\":", pad);
+                print_block(body, indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::Loop { body } => {
+                println!("{}{{ \"type\": \"Loop\",", pad);
+                println!("{}  \"body\":", pad);
+                print_block(body, indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::For { variable, iterable, body, .. } => {
+                println!("{}{{ \"type\": \"For\", \"variable\": \"{}\",", pad, variable);
+                println!("{}  \"iterable\":", pad);
+                iterable.print(indent + 4);
+                println!("{}  \"body\":", pad);
+                print_block(body, indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Expression::Assign { target, operator, value, .. } => {
+                println!("{}{{ \"type\": \"Assign\", \"operator\": \"{}\",", pad, operator);
+                println!("{}  \"target\":", pad);
+                target.print(indent + 4);
+                println!("{}  \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
+            }
+        }
+    }
+}
+
+impl Pattern {
+    pub fn print(&self, indent: usize) {
+        let pad = " ".repeat(indent);
+
+        match self {
+            Pattern::Wildcard => println!("{}{{ \"type\": \"WildcardPattern\" }}", pad),
+
+            Pattern::Binding(name) => {
+                println!("{}{{ \"type\": \"BindingPattern\", \"name\": \"{}\" }}", pad, name);
+            }
+
+            Pattern::Literal(value) => {
+                println!("{}{{ \"type\": \"LiteralPattern\", \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Pattern::Array(elements) => {
+                println!("{}{{ \"type\": \"ArrayPattern\", \"elements\": [", pad);
+                for element in elements {
+                    element.print(indent + 4);
+                }
+                println!("{}]}}", pad);
+            }
+
+            Pattern::Rest(name) => match name {
+                Some(name) => println!("{}{{ \"type\": \"RestPattern\", \"name\": \"{}\" }}", pad, name),
+                None => println!("{}{{ \"type\": \"RestPattern\" }}", pad),
+            },
+
+            Pattern::Hash(pairs) => {
+                println!("{}{{ \"type\": \"HashPattern\", \"pairs\": [", pad);
+                for (key, value) in pairs {
+                    key.print(indent + 4);
+                    value.print(indent + 4);
+                }
+                println!("{}]}}", pad);
+            }
+
+            Pattern::Variant { enum_name, variant, fields, .. } => {
+                let path = match enum_name {
+                    Some(enum_name) => format!("{}::{}", enum_name, variant),
+                    None => variant.clone(),
+                };
+                println!("{}{{ \"type\": \"VariantPattern\", \"variant\": \"{}\", \"fields\": [", pad, path);
+                for field in fields {
+                    field.print(indent + 4);
+                }
+                println!("{}]}}", pad);
+            }
         }
     }
 }
@@ -73,35 +655,181 @@ impl Expression {
 
 impl Statement {
     pub fn print(&self) {
+        self.print_indented(0);
+    }
+
+    fn print_indented(&self, indent: usize) {
+        let pad = " ".repeat(indent);
+
         match self {
-            Statement::LetStatement { name, value } => {
-                println!("{{");
-                println!("  \"type\": \"LetStatement\",");
-                println!("  \"name\": \"{}\",", name);
-                println!("  \"value\":");
-                value.print(4);
-                println!("}}");
+            Statement::LetStatement { name, annotation, value, .. } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"LetStatement\",", pad);
+                println!("{}  \"name\": \"{}\",", pad, name);
+                if let Some(annotation) = annotation {
+                    println!("{}  \"annotation\": \"{}\",", pad, annotation.ty);
+                }
+                println!("{}  \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Statement::LetPattern { pattern, value, .. } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"LetPattern\",", pad);
+                println!("{}  \"pattern\":", pad);
+                pattern.print(indent + 4);
+                println!("{}  \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Statement::ConstStatement { name, annotation, value, .. } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"ConstStatement\",", pad);
+                println!("{}  \"name\": \"{}\",", pad, name);
+                if let Some(annotation) = annotation {
+                    println!("{}  \"annotation\": \"{}\",", pad, annotation.ty);
+                }
+                println!("{}  \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
             }
 
             Statement::ReturnStatement { value } => {
-                println!("{{");
-                println!("  \"type\": \"ReturnStatement\",");
-                println!("  \"value\":");
-                value.print(4);
-                println!("}}");
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"ReturnStatement\",", pad);
+                println!("{}  \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Statement::Throw { value, .. } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"Throw\",", pad);
+                println!("{}  \"value\":", pad);
+                value.print(indent + 4);
+                println!("{}}}", pad);
             }
 
             Statement::ExpressionStatement(expr) => {
-                println!("{{");
-                println!("  \"type\": \"ExpressionStatement\",");
-                println!("  \"value\":");
-                expr.print(4);
-                println!("}}");
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"ExpressionStatement\",", pad);
+                println!("{}  \"value\":", pad);
+                expr.print(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Statement::Block(statements) => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"Block\",", pad);
+                println!("{}  \"statements\":", pad);
+                print_block(statements, indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Statement::StructDeclaration { name, fields } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"StructDeclaration\",", pad);
+                println!("{}  \"name\": \"{}\",", pad, name);
+                println!("{}  \"fields\": {:?}", pad, fields);
+                println!("{}}}", pad);
+            }
+
+            Statement::EnumDeclaration { name, variants } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"EnumDeclaration\",", pad);
+                println!("{}  \"name\": \"{}\",", pad, name);
+                println!("{}  \"variants\": [", pad);
+                for variant in variants {
+                    println!("{}    {{ \"name\": \"{}\", \"fields\": {:?} }}", pad, variant.name, variant.fields);
+                }
+                println!("{}  ]", pad);
+                println!("{}}}", pad);
+            }
+
+            Statement::Impl { type_name, trait_name, methods, .. } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"Impl\",", pad);
+                println!("{}  \"type_name\": \"{}\",", pad, type_name);
+                if let Some(trait_name) = trait_name {
+                    println!("{}  \"trait\": \"{}\",", pad, trait_name);
+                }
+                println!("{}  \"methods\": [", pad);
+                for method in methods {
+                    println!("{}    {{ \"name\": \"{}\", \"parameters\": {:?},", pad, method.name, method.parameters);
+                    println!("{}      \"body\":", pad);
+                    print_block(&method.body, indent + 8);
+                    println!("{}    }}", pad);
+                }
+                println!("{}  ]", pad);
+                println!("{}}}", pad);
+            }
+
+            Statement::Trait { name, methods, .. } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"Trait\",", pad);
+                println!("{}  \"name\": \"{}\",", pad, name);
+                println!("{}  \"methods\": [", pad);
+                for method in methods {
+                    println!("{}    {{ \"name\": \"{}\", \"parameters\": {:?},", pad, method.name, method.parameters);
+                    match &method.default {
+                        Some(body) => {
+                            println!("{}      \"default\":", pad);
+                            print_block(body, indent + 8);
+                        }
+                        None => println!("{}      \"default\": null", pad),
+                    }
+                    println!("{}    }}", pad);
+                }
+                println!("{}  ]", pad);
+                println!("{}}}", pad);
+            }
+
+            Statement::Import { path, alias, .. } => {
+                println!("{}{{ \"type\": \"Import\", \"path\": {:?}, \"alias\": \"{}\" }}", pad, path, alias);
+            }
+
+            Statement::Export(statement) => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"Export\",", pad);
+                println!("{}  \"declaration\":", pad);
+                statement.print_indented(indent + 4);
+                println!("{}}}", pad);
+            }
+
+            Statement::Break { value } => {
+                println!("{}{{", pad);
+                println!("{}  \"type\": \"Break\",", pad);
+                if let Some(value) = value {
+                    println!("{}  \"value\":", pad);
+                    value.print(indent + 4);
+                }
+                println!("{}}}", pad);
+            }
+
+            Statement::Continue => {
+                println!("{}{{ \"type\": \"Continue\" }}", pad);
             }
         }
     }
 }
 
+// How an optional annotation prints; a missing one is `any`.
+fn annotation_name(annotation: Option<&TypeAnnotation>) -> String {
+    annotation.map_or_else(|| "any".to_string(), |annotation| annotation.ty.to_string())
+}
+
+fn print_block(statements: &Block, indent: usize) {
+    let pad = " ".repeat(indent);
+
+    println!("{}[", pad);
+    for stmt in statements {
+        stmt.print_indented(indent + 2);
+    }
+    println!("{}]", pad);
+}
+
 
 
 
@@ -0,0 +1,1272 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::ast::{
+    Block, Expression, InterpolationPart, Method, Pattern, Statement, TraitMethod, TypeAnnotation, TypeExpr, TypeParameter,
+};
+use crate::interpreter::PRELUDE;
+use crate::lexer::{Lexer, Span};
+use crate::parser::Parser;
+
+// The static type of an expression. `Any` is what the checker cannot know,
+// like what a builtin returns: it fits everywhere, and nothing is reported
+// about it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+    Null,
+    Array(Box<Type>),
+    Hash(Box<Type>, Box<Type>),
+    Function(Vec<Type>, Box<Type>),
+    // An instance of a struct, by the struct's name.
+    Named(String),
+    // A type that is not known yet, to be found by unification.
+    Var(usize),
+    // A function's type parameter, like `T` in `fn<T: Show>(x: T)`. Inside
+    // the function it is only itself; each call gets a fresh variable.
+    Param(usize, String),
+    Any,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::String => write!(f, "string"),
+            Type::Null => write!(f, "null"),
+            Type::Array(element) => write!(f, "[{}]", element),
+            Type::Hash(key, value) => write!(f, "{{{}: {}}}", key, value),
+            Type::Function(parameters, ret) => {
+                write!(f, "fn(")?;
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", parameter)?;
+                }
+                write!(f, ") -> {}", ret)
+            }
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Var(n) if *n < 26 => write!(f, "'{}", (b'a' + *n as u8) as char),
+            Type::Var(n) => write!(f, "'t{}", n),
+            Type::Param(_, name) => write!(f, "{}", name),
+            Type::Any => write!(f, "any"),
+        }
+    }
+}
+
+// A mistake found before the program runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+    pub severity: Severity,
+}
+
+// Only a mistake against an annotation or a declaration keeps a program
+// from running. What inference alone finds is a warning: the language is
+// dynamic, and a check like `x == null` can make such code fine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+// Checks a whole program on its own.
+pub fn check_program(program: &[Statement]) -> Vec<TypeError> {
+    Checker::new().check(program)
+}
+
+// Infers the types of programs, Hindley-Milner style, and checks them
+// against their annotations. Where the language is too dynamic to follow,
+// like values that are reassigned something else, the checker falls back
+// to `any` rather than reporting code that would run.
+//
+// A checker remembers the globals of the programs it has checked, so the
+// REPL can check each line as it comes. A program with errors does not run,
+// so the checker forgets it too.
+#[derive(Clone)]
+pub struct Checker {
+    // Innermost scope last.
+    scopes: Vec<HashMap<String, Binding>>,
+    // Struct and enum names, which annotations may use.
+    types: HashSet<String>,
+    // Traits by name, and the (type, trait) pairs impls have implemented.
+    traits: HashMap<String, TraitInfo>,
+    impls: HashSet<(String, String)>,
+    // The bounds of every type parameter, by its number, and the names of
+    // those in scope, innermost function last.
+    params: Vec<Vec<String>>,
+    type_parameters: Vec<HashMap<String, usize>>,
+    // The variables calls gave type parameters, with the parameter, so the
+    // call can check its bounds once the arguments decide them.
+    pending: Vec<(Type, usize)>,
+    // What each type variable has been unified with, and where.
+    substitution: Vec<Option<Type>>,
+    origins: Vec<Option<Span>>,
+    // The variables arithmetic with an int or a float has used, which can
+    // only be an int or a float themselves.
+    numeric: Vec<bool>,
+    // The variables bound by the unification in progress, so it can be
+    // undone if it fails.
+    trail: Vec<usize>,
+    // The function being checked innermost last.
+    returns: Vec<Return>,
+    errors: Vec<TypeError>,
+}
+
+// A binding's type, with the variables and type parameters each use gets
+// fresh copies of.
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    params: Vec<usize>,
+    ty: Type,
+}
+
+impl Scheme {
+    fn mono(ty: Type) -> Self {
+        Scheme { vars: vec![], params: vec![], ty }
+    }
+}
+
+#[derive(Clone)]
+struct TraitInfo {
+    // Each method's name, and whether the trait gives it a default body.
+    methods: Vec<(String, bool)>,
+    // Declared by the prelude, so built-in types implement it natively.
+    builtin: bool,
+}
+
+#[derive(Clone)]
+struct Binding {
+    scheme: Scheme,
+    // An annotated binding keeps its type. One whose type was inferred
+    // becomes `any` when it is assigned something else.
+    annotated: bool,
+}
+
+#[derive(Clone)]
+struct Return {
+    ty: Type,
+    annotation: Option<TypeAnnotation>,
+    // Set when the function returns values that do not unify.
+    dynamic: bool,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        let mut checker = Checker {
+            scopes: vec![HashMap::new()],
+            types: HashSet::new(),
+            traits: HashMap::new(),
+            impls: HashSet::new(),
+            params: vec![],
+            type_parameters: vec![],
+            pending: vec![],
+            substitution: vec![],
+            origins: vec![],
+            numeric: vec![],
+            trail: vec![],
+            returns: vec![],
+            errors: vec![],
+        };
+        // For its types and traits. Only traits declared here are builtin.
+        let prelude = Parser::new(Lexer::new(PRELUDE)).parse_program();
+        checker.check(&prelude);
+        for info in checker.traits.values_mut() {
+            info.builtin = true;
+        }
+        checker
+    }
+
+    pub fn check(&mut self, program: &[Statement]) -> Vec<TypeError> {
+        let before = self.clone();
+        // Types, traits and impls can be used before their declaration.
+        for statement in program {
+            let statement = match statement {
+                Statement::Export(statement) => statement,
+                statement => statement,
+            };
+            match statement {
+                Statement::StructDeclaration { name, .. } | Statement::EnumDeclaration { name, .. } => {
+                    self.types.insert(name.clone());
+                }
+                Statement::Trait { name, methods, .. } => self.declare_trait(name, methods),
+                Statement::Impl { type_name, trait_name: Some(trait_name), .. } => {
+                    self.impls.insert((type_name.clone(), trait_name.clone()));
+                }
+                _ => {}
+            }
+        }
+
+        for statement in program {
+            self.statement(statement);
+        }
+        self.pending.clear();
+        let errors = std::mem::take(&mut self.errors);
+        if errors.iter().any(|err| err.severity == Severity::Error) {
+            *self = before;
+        }
+        errors
+    }
+
+    // The inferred type of a global, as messages show it.
+    pub fn type_of(&self, name: &str) -> Option<String> {
+        let binding = self.scopes[0].get(name)?;
+        let [shown] = self.show([&binding.scheme.ty], Span::default());
+        Some(shown)
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(TypeError { message, span, severity: Severity::Error });
+    }
+
+    fn warning(&mut self, message: String, span: Span) {
+        self.errors.push(TypeError { message, span, severity: Severity::Warning });
+    }
+
+    fn define(&mut self, name: &str, scheme: Scheme, annotated: bool) -> Option<Binding> {
+        let scope = self.scopes.last_mut().expect("the global scope is never popped");
+        scope.insert(name.to_string(), Binding { scheme, annotated })
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+
+    // Checks `statements` in a scope of their own and gives the type of
+    // the block's value.
+    fn block(&mut self, statements: &Block) -> Type {
+        self.block_with(vec![], statements)
+    }
+
+    // Like `block`, with `bindings` defined in the new scope first.
+    fn block_with(&mut self, bindings: Vec<(String, Type, bool)>, statements: &Block) -> Type {
+        self.scopes.push(HashMap::new());
+        for (name, ty, annotated) in bindings {
+            self.define(&name, Scheme::mono(ty), annotated);
+        }
+        let ty = self.statements(statements);
+        self.scopes.pop();
+        ty
+    }
+
+    // A block's value is its last statement's, as in the interpreter.
+    fn statements(&mut self, statements: &Block) -> Type {
+        let mut ty = Type::Null;
+        for statement in statements {
+            ty = self.statement(statement);
+        }
+        ty
+    }
+
+    // The type of the statement's value. Statements that jump elsewhere,
+    // like `return`, give a fresh variable, which fits whatever the other
+    // branches give.
+    fn statement(&mut self, statement: &Statement) -> Type {
+        match statement {
+            Statement::LetStatement { name, annotation, value, .. } | Statement::ConstStatement { name, annotation, value, .. } => {
+                self.let_statement(name, annotation.as_ref(), value);
+                Type::Null
+            }
+            Statement::LetPattern { pattern, value, .. } => {
+                self.expression(value);
+                let mut names = vec![];
+                pattern_names(pattern, &mut names);
+                for name in names {
+                    self.define(&name, Scheme::mono(Type::Any), false);
+                }
+                Type::Null
+            }
+            Statement::ReturnStatement { value } => {
+                let found = self.expression(value);
+                self.returned(found);
+                self.fresh()
+            }
+            Statement::Throw { value, .. } => {
+                self.expression(value);
+                self.fresh()
+            }
+            Statement::ExpressionStatement(expression) => self.expression(expression),
+            Statement::Block(statements) => self.block(statements),
+            Statement::StructDeclaration { name, .. } | Statement::EnumDeclaration { name, .. } => {
+                self.types.insert(name.clone());
+                self.define(name, Scheme::mono(Type::Any), false);
+                Type::Null
+            }
+            Statement::Impl { type_name, trait_name, methods, span } => {
+                if let Some(trait_name) = trait_name {
+                    self.impl_trait(type_name, trait_name, methods, *span);
+                }
+                for Method { type_parameters, parameters, parameter_types, return_type, body, .. } in methods {
+                    self.function(type_parameters, parameters, parameter_types, return_type.as_ref(), body);
+                }
+                Type::Null
+            }
+            Statement::Trait { name, methods, .. } => {
+                self.declare_trait(name, methods);
+                for method in methods {
+                    if let Some(body) = &method.default {
+                        let TraitMethod { type_parameters, parameters, parameter_types, return_type, .. } = method;
+                        self.function(type_parameters, parameters, parameter_types, return_type.as_ref(), body);
+                    }
+                }
+                self.define(name, Scheme::mono(Type::Any), false);
+                Type::Null
+            }
+            Statement::Import { alias, .. } => {
+                self.define(alias, Scheme::mono(Type::Any), false);
+                Type::Null
+            }
+            Statement::Export(statement) => self.statement(statement),
+            Statement::Break { value } => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+                self.fresh()
+            }
+            Statement::Continue => self.fresh(),
+        }
+    }
+
+    fn declare_trait(&mut self, name: &str, methods: &[TraitMethod]) {
+        let methods = methods.iter().map(|method| (method.name.clone(), method.default.is_some())).collect();
+        self.traits.insert(name.to_string(), TraitInfo { methods, builtin: false });
+    }
+
+    // Checks that `impl trait_name for type_name` has the trait's methods,
+    // and only those. The messages are the interpreter's.
+    fn impl_trait(&mut self, type_name: &str, trait_name: &str, methods: &[Method], span: Span) {
+        let Some(info) = self.traits.get(trait_name) else {
+            self.error(format!("unknown trait '{}'", trait_name), span);
+            return;
+        };
+        let member = |name: &str| info.methods.iter().any(|(method, _)| method == name);
+        let message = if let Some(extra) = methods.iter().find(|method| !member(&method.name)) {
+            format!("method '{}' is not a member of trait {}", extra.name, trait_name)
+        } else if let Some((missing, _)) =
+            info.methods.iter().find(|(name, default)| !default && !methods.iter().any(|method| method.name == *name))
+        {
+            format!("impl {} for {} is missing method '{}'", trait_name, type_name, missing)
+        } else {
+            return;
+        };
+        self.error(message, span);
+    }
+
+    fn let_statement(&mut self, name: &str, annotation: Option<&TypeAnnotation>, value: &Expression) {
+        let expected = annotation.map(|annotation| self.resolve(annotation));
+        let function = matches!(value, Expression::FunctionLiteral { .. });
+
+        // A function can call itself, before its type is known.
+        let own = match (function, &expected) {
+            (false, _) => None,
+            (true, Some(expected)) => Some(expected.clone()),
+            (true, None) => Some(self.fresh()),
+        };
+        let shadowed = own.clone().map(|ty| self.define(name, Scheme::mono(ty), annotation.is_some()));
+
+        let found = self.expression(value);
+
+        if let Some(shadowed) = shadowed {
+            let scope = self.scopes.last_mut().expect("the global scope is never popped");
+            scope.remove(name);
+            if let Some(shadowed) = shadowed {
+                scope.insert(name.to_string(), shadowed);
+            }
+        }
+
+        match (annotation, expected) {
+            (Some(annotation), Some(expected)) => {
+                self.expect(&expected, &found, annotation.span, Severity::Error, |expected, found| format!("expected {}, found {}", expected, found));
+                self.define(name, Scheme::mono(expected), true);
+            }
+            _ => {
+                // What the recursive calls inferred is part of the function's type.
+                if let Some(own) = own {
+                    self.unify(&own, &found, None);
+                }
+                // Any function value is generic, not only a literal: an alias
+                // such as `let f = id;`, or the result of a partial application.
+                let found = self.shallow(&found);
+                let scheme = match found {
+                    Type::Function(..) => self.generalize(found),
+                    found => Scheme::mono(widen(found)),
+                };
+                self.define(name, scheme, false);
+            }
+        }
+    }
+
+    // The type of what a `return` or the end of a function body gives back.
+    fn returned(&mut self, found: Type) {
+        let Some(Return { ty, annotation, .. }) = self.returns.last() else {
+            return;
+        };
+        let expected = ty.clone();
+        match annotation.clone() {
+            Some(annotation) => {
+                self.expect(&expected, &found, annotation.span, Severity::Error, |expected, found| {
+                    format!("function returns {}, but its return type is {}", found, expected)
+                });
+            }
+            None => {
+                if !self.unify(&expected, &found, None) {
+                    if let Some(function) = self.returns.last_mut() {
+                        function.dynamic = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Type {
+        match expression {
+            Expression::Identifier { name, .. } => match self.lookup(name) {
+                Some(binding) => {
+                    let scheme = binding.scheme.clone();
+                    self.instantiate(&scheme)
+                }
+                None => Type::Any,
+            },
+            Expression::NumberLiteral(_) => Type::Int,
+            Expression::FloatLiteral(_) => Type::Float,
+            Expression::StringLiteral(_) => Type::String,
+            Expression::BooleanLiteral(_) => Type::Bool,
+            Expression::NullLiteral => Type::Null,
+            Expression::Interpolated(parts) => {
+                for part in parts {
+                    if let InterpolationPart::Expr(expression) = part {
+                        self.expression(expression);
+                    }
+                }
+                Type::String
+            }
+            Expression::ArrayLiteral(elements) => {
+                let types: Vec<Type> = elements.iter().map(|element| self.expression(element)).collect();
+                Type::Array(Box::new(self.join(types)))
+            }
+            Expression::HashLiteral(pairs) => {
+                let mut keys = vec![];
+                let mut values = vec![];
+                for (key, value, _) in pairs {
+                    keys.push(self.expression(key));
+                    values.push(self.expression(value));
+                }
+                Type::Hash(Box::new(self.join(keys)), Box::new(self.join(values)))
+            }
+            Expression::Prefix { operator, right, span } => {
+                let right = self.expression(right);
+                match (operator.as_str(), self.shallow(&right)) {
+                    ("!", _) => Type::Bool,
+                    ("-", ty @ (Type::Int | Type::Float | Type::Any)) => ty,
+                    // Either an int or a float.
+                    ("-", Type::Var(_)) => Type::Any,
+                    _ => {
+                        let [right] = self.show([&right], *span);
+                        self.warning(format!("cannot apply '{}' to {}", operator, right), *span);
+                        Type::Any
+                    }
+                }
+            }
+            Expression::Infix { left, operator, right, span } => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.operator(operator, left, right, *span)
+            }
+            Expression::Assign { target, operator, value, span } => self.assign(target, operator, value, *span),
+            Expression::FunctionLiteral { type_parameters, parameters, parameter_types, return_type, body } => {
+                self.function(type_parameters, parameters, parameter_types, return_type.as_ref(), body)
+            }
+            Expression::Call { function, arguments, span } => {
+                // `p.norm()` passes `p` too, so its arguments do not line up.
+                if let Expression::Field { object, field, span } = &**function {
+                    let object = self.expression(object);
+                    if let Type::Param(param, name) = self.shallow(&object) {
+                        if !self.has_method(param, field) {
+                            self.error(format!("{} has no method '{}'", name, field), *span);
+                        }
+                    } else {
+                        self.field(&object, field, *span);
+                    }
+                    for argument in arguments {
+                        self.expression(argument);
+                    }
+                    return Type::Any;
+                }
+
+                let mark = self.pending.len();
+                let callee = self.expression(function);
+                let arguments: Vec<Type> = arguments.iter().map(|argument| self.expression(argument)).collect();
+                let ret = self.call(callee, &arguments, *span);
+
+                // What the arguments made of the callee's type parameters.
+                for (ty, param) in self.pending.split_off(mark) {
+                    for bound in self.params[param].clone() {
+                        if !self.implements(&ty, &bound) {
+                            let [ty] = self.show([&ty], *span);
+                            self.error(format!("{} does not implement {}", ty, bound), *span);
+                        }
+                    }
+                }
+                ret
+            }
+            Expression::StructLiteral { name, fields, .. } => {
+                for (_, value) in fields {
+                    self.expression(value);
+                }
+                Type::Named(name.clone())
+            }
+            Expression::Path { object, .. } => {
+                self.expression(object);
+                Type::Any
+            }
+            Expression::Field { object, field, span } => {
+                let object = self.expression(object);
+                self.field(&object, field, *span);
+                Type::Any
+            }
+            Expression::Index { left, index, span } => {
+                let left = self.expression(left);
+                let index = self.expression(index);
+                self.index(left, index, *span)
+            }
+            Expression::Slice { left, start, end, span } => {
+                let left = self.expression(left);
+                for bound in [start, end].into_iter().flatten() {
+                    let bound = self.expression(bound);
+                    self.expect_index(&bound, *span);
+                }
+                match self.shallow(&left) {
+                    Type::Array(_) | Type::String | Type::Any => left,
+                    Type::Var(_) => Type::Any,
+                    _ => {
+                        let [left] = self.show([&left], *span);
+                        self.warning(format!("cannot slice {}", left), *span);
+                        Type::Any
+                    }
+                }
+            }
+            Expression::If { condition, consequence, alternative } => {
+                self.expression(condition);
+                let consequence = self.block(consequence);
+                let alternative = match alternative {
+                    Some(alternative) => self.block(alternative),
+                    None => Type::Null,
+                };
+                self.join(vec![consequence, alternative])
+            }
+            Expression::Match { subject, arms, .. } => {
+                self.expression(subject);
+                let mut types = vec![];
+                for arm in arms {
+                    let mut names = vec![];
+                    pattern_names(&arm.pattern, &mut names);
+
+                    let bindings = names.into_iter().map(|name| (name, Binding { scheme: Scheme::mono(Type::Any), annotated: false }));
+                    self.scopes.push(bindings.collect());
+                    if let Some(guard) = &arm.guard {
+                        self.expression(guard);
+                    }
+                    types.push(self.statements(&arm.body));
+                    self.scopes.pop();
+                }
+                self.join(types)
+            }
+            Expression::While { condition, body } => {
+                self.expression(condition);
+                self.block(body);
+                Type::Any
+            }
+            Expression::Loop { body } => {
+                self.block(body);
+                Type::Any
+            }
+            Expression::For { variable, iterable, body, span } => {
+                let iterable = self.expression(iterable);
+                let element = match self.shallow(&iterable) {
+                    Type::Array(element) => *element,
+                    Type::Hash(key, _) => *key,
+                    Type::String => Type::String,
+                    Type::Var(_) | Type::Any => Type::Any,
+                    _ => {
+                        let [iterable] = self.show([&iterable], *span);
+                        self.warning(format!("cannot iterate over {}", iterable), *span);
+                        Type::Any
+                    }
+                };
+                self.block_with(vec![(variable.clone(), element, false)], body);
+                Type::Any
+            }
+            Expression::Try { body, catch, finally } => {
+                let mut types = vec![self.block(body)];
+                if let Some(catch) = catch {
+                    let bindings = catch.name.iter().map(|name| (name.clone(), Type::Any, false)).collect();
+                    types.push(self.block_with(bindings, &catch.body));
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+                self.join(types)
+            }
+        }
+    }
+
+    // The type of calling `callee` with `arguments`.
+    fn call(&mut self, callee: Type, arguments: &[Type], span: Span) -> Type {
+        match self.shallow(&callee) {
+            Type::Function(parameters, ret) => {
+                if parameters.len() != arguments.len() {
+                    let message = format!("expected {} arguments but got {}", parameters.len(), arguments.len());
+                    self.warning(message, span);
+                } else {
+                    for (i, (parameter, argument)) in parameters.iter().zip(arguments).enumerate() {
+                        // An unannotated parameter is still a variable, even
+                        // once inference has bound it.
+                        let severity = match parameter {
+                            Type::Var(_) => Severity::Warning,
+                            _ => Severity::Error,
+                        };
+                        self.expect(parameter, argument, span, severity, |expected, found| {
+                            format!("argument {}: expected {}, found {}", i + 1, expected, found)
+                        });
+                    }
+                }
+                *ret
+            }
+            // Calling it is what says it is a function.
+            Type::Var(_) => {
+                // Through variables, so later calls can say where
+                // the parameter types came from.
+                let parameters: Vec<Type> = arguments.iter().map(|_| self.fresh()).collect();
+                for (parameter, argument) in parameters.iter().zip(arguments) {
+                    self.unify(parameter, argument, Some(span));
+                }
+                let ret = self.fresh();
+                let called = Type::Function(parameters, Box::new(ret.clone()));
+                self.expect(&callee, &called, span, Severity::Warning, |expected, found| format!("cannot call {} as {}", expected, found));
+                ret
+            }
+            Type::Any => Type::Any,
+            _ => {
+                let [callee] = self.show([&callee], span);
+                self.warning(format!("cannot call {}, it is not a function", callee), span);
+                Type::Any
+            }
+        }
+    }
+
+    // The type of `left <operator> right`, following eval_infix_expression.
+    fn operator(&mut self, operator: &str, left: Type, right: Type, span: Span) -> Type {
+        let comparison = matches!(operator, "<" | ">" | "<=" | ">=");
+        let ty = match (self.shallow(&left), self.shallow(&right)) {
+            // Any two values can be compared for equality.
+            _ if matches!(operator, "==" | "!=" | "&&" | "||") => Some(Type::Bool),
+            // Type parameters only compare if they are bounded by Ord.
+            (Type::Param(..), _) | (_, Type::Param(..)) if comparison => {
+                let ordered = self.implements(&left, "Ord") && self.implements(&right, "Ord");
+                (ordered && self.unify(&left, &right, Some(span))).then_some(Type::Bool)
+            }
+            (Type::Var(_), _) | (_, Type::Var(_)) if comparison => Some(Type::Bool),
+            // Structs and enums compare through their `cmp` method.
+            (Type::Named(l), Type::Named(r)) if comparison => (l == r && self.implements(&left, "Ord")).then_some(Type::Bool),
+            // Only ints can be shifted, so both sides are ints.
+            (Type::Var(_), _) | (_, Type::Var(_)) if matches!(operator, "<<" | ">>") => {
+                let ints = self.unify(&Type::Int, &left, Some(span)) && self.unify(&Type::Int, &right, Some(span));
+                ints.then_some(Type::Int)
+            }
+            // Both ints or both bools.
+            (Type::Var(_), _) | (_, Type::Var(_)) if matches!(operator, "&" | "|" | "^") => {
+                let same = self.unify(&left, &right, Some(span));
+                let ty = self.shallow(&left);
+                (same && matches!(ty, Type::Int | Type::Bool | Type::Var(_) | Type::Any)).then_some(ty)
+            }
+            // A string only adds to a string.
+            (Type::Var(_), Type::String) | (Type::String, Type::Var(_)) if operator == "+" => {
+                let strings = self.unify(&left, &right, Some(span));
+                strings.then_some(Type::String)
+            }
+            // Arithmetic that could be on ints or floats. With a number on
+            // the other side, the variable has to be a number too.
+            (Type::Var(var), known) | (known, Type::Var(var)) => match known {
+                Type::Int | Type::Float => {
+                    self.numeric[var] = true;
+                    Some(if known == Type::Float { Type::Float } else { Type::Any })
+                }
+                Type::Var(_) | Type::Any => Some(Type::Any),
+                _ => None,
+            },
+            (left, right) => operator_type(operator, &left, &right),
+        };
+
+        ty.unwrap_or_else(|| {
+            let [left, right] = self.show([&left, &right], span);
+            self.warning(format!("cannot apply '{}' to {} and {}", operator, left, right), span);
+            Type::Any
+        })
+    }
+
+    fn assign(&mut self, target: &Expression, operator: &str, value: &Expression, span: Span) -> Type {
+        let mut found = self.expression(value);
+        if operator != "=" {
+            let current = self.expression(target);
+            found = self.operator(operator.trim_end_matches('='), current, found, span);
+        }
+
+        match target {
+            Expression::Identifier { name, .. } => {
+                let Some(binding) = self.lookup(name) else {
+                    return found;
+                };
+                let annotated = binding.annotated;
+                let scheme = binding.scheme.clone();
+                let expected = self.instantiate(&scheme);
+                if annotated {
+                    self.expect(&expected, &found, span, Severity::Error, |expected, found| format!("expected {}, found {}", expected, found));
+                } else if !self.unify(&expected, &found, Some(span)) {
+                    if let Some(binding) = self.lookup(name) {
+                        binding.scheme = Scheme::mono(Type::Any);
+                    }
+                }
+            }
+            Expression::Index { left, index, span } => {
+                let left = self.expression(left);
+                let index = self.expression(index);
+                let expected = self.index(left, index, *span);
+                self.expect(&expected, &found, *span, Severity::Warning, |expected, found| format!("expected {}, found {}", expected, found));
+            }
+            Expression::Field { object, .. } => {
+                self.expression(object);
+            }
+            // The parser only accepts the targets above.
+            _ => {}
+        }
+        found
+    }
+
+    // Checks that `object` can have a field or method named `field`.
+    fn field(&mut self, object: &Type, field: &str, span: Span) {
+        if !matches!(self.shallow(object), Type::Named(_) | Type::Var(_) | Type::Any) {
+            let [object] = self.show([object], span);
+            self.warning(format!("cannot access field '{}' on {}", field, object), span);
+        }
+    }
+
+    // Whether one of the traits bounding type parameter `param` has `method`.
+    fn has_method(&self, param: usize, method: &str) -> bool {
+        self.params[param]
+            .iter()
+            .filter_map(|bound| self.traits.get(bound))
+            .any(|info| info.methods.iter().any(|(name, _)| name == method))
+    }
+
+    // Whether `ty` implements the trait named `bound`. Values of built-in
+    // types implement the prelude's traits for what they support natively,
+    // as in the interpreter.
+    fn implements(&self, ty: &Type, bound: &str) -> bool {
+        let builtin = self.traits.get(bound).is_some_and(|info| info.builtin);
+        match self.shallow(ty) {
+            Type::Var(_) | Type::Any => true,
+            Type::Param(param, _) => self.params[param].iter().any(|name| name == bound),
+            Type::Named(name) => self.impls.contains(&(name, bound.to_string())),
+            _ if !builtin => false,
+            Type::Int | Type::String => true,
+            Type::Float => bound != "Hash",
+            Type::Bool => bound != "Ord",
+            Type::Array(_) | Type::Hash(..) | Type::Null => matches!(bound, "Eq" | "Display"),
+            Type::Function(..) => false,
+        }
+    }
+
+    fn index(&mut self, left: Type, index: Type, span: Span) -> Type {
+        match self.shallow(&left) {
+            Type::Array(element) => {
+                self.expect_index(&index, span);
+                *element
+            }
+            Type::String => {
+                self.expect_index(&index, span);
+                Type::String
+            }
+            Type::Hash(key, value) => {
+                self.expect(&key, &index, span, Severity::Warning, |key, index| format!("expected {} key, found {}", key, index));
+                *value
+            }
+            Type::Var(_) | Type::Any => Type::Any,
+            _ => {
+                let [left] = self.show([&left], span);
+                self.warning(format!("cannot index {}", left), span);
+                Type::Any
+            }
+        }
+    }
+
+    fn expect_index(&mut self, index: &Type, span: Span) {
+        self.expect(&Type::Int, index, span, Severity::Warning, |_, index| format!("index must be an integer, not {}", index));
+    }
+
+    // A function literal or method. Unannotated parameters start out as
+    // fresh variables, which the body's uses of them decide.
+    fn function(
+        &mut self,
+        type_parameters: &[TypeParameter],
+        parameters: &[String],
+        parameter_types: &[Option<TypeAnnotation>],
+        return_type: Option<&TypeAnnotation>,
+        body: &Block,
+    ) -> Type {
+        let mut scope = HashMap::new();
+        for TypeParameter { name, bounds, span } in type_parameters {
+            for bound in bounds {
+                if !self.traits.contains_key(bound) {
+                    self.error(format!("unknown trait '{}'", bound), *span);
+                }
+            }
+            scope.insert(name.clone(), self.params.len());
+            self.params.push(bounds.clone());
+        }
+        self.type_parameters.push(scope);
+
+        let types: Vec<Type> = parameter_types
+            .iter()
+            .map(|annotation| match annotation {
+                Some(annotation) => self.resolve(annotation),
+                None => self.fresh(),
+            })
+            .collect();
+        let ret = match return_type {
+            Some(annotation) => self.resolve(annotation),
+            None => self.fresh(),
+        };
+
+        let bindings = parameters
+            .iter()
+            .zip(types.iter().zip(parameter_types))
+            .map(|(name, (ty, annotation))| (name.clone(), ty.clone(), annotation.is_some()))
+            .collect();
+
+        self.returns.push(Return { ty: ret.clone(), annotation: return_type.cloned(), dynamic: false });
+        let found = self.block_with(bindings, body);
+        self.returned(found);
+        let function = self.returns.pop().expect("pushed above");
+        self.type_parameters.pop();
+
+        let ret = if function.dynamic { Type::Any } else { ret };
+        Type::Function(types, Box::new(ret))
+    }
+
+    // Unifies `found` with `expected`, reporting `message(expected, found)`
+    // at `span` if they do not.
+    fn expect(
+        &mut self,
+        expected: &Type,
+        found: &Type,
+        span: Span,
+        severity: Severity,
+        message: impl FnOnce(&str, &str) -> String,
+    ) -> bool {
+        if self.unify(expected, found, Some(span)) {
+            return true;
+        }
+        let [expected, found] = self.show([expected, found], span);
+        self.errors.push(TypeError { message: message(&expected, &found), span, severity });
+        false
+    }
+
+    // The type an annotation names.
+    fn resolve(&mut self, annotation: &TypeAnnotation) -> Type {
+        match self.resolve_type(&annotation.ty) {
+            Ok(ty) => ty,
+            Err(name) => {
+                self.error(format!("unknown type '{}'", name), annotation.span);
+                Type::Any
+            }
+        }
+    }
+
+    fn resolve_type(&self, ty: &TypeExpr) -> Result<Type, String> {
+        Ok(match ty {
+            TypeExpr::Named(name) => match name.as_str() {
+                "int" => Type::Int,
+                "float" => Type::Float,
+                "bool" => Type::Bool,
+                "string" => Type::String,
+                "null" => Type::Null,
+                "any" => Type::Any,
+                _ => match self.type_parameter(name) {
+                    Some(param) => Type::Param(param, name.clone()),
+                    None if self.types.contains(name) => Type::Named(name.clone()),
+                    None => return Err(name.clone()),
+                },
+            },
+            TypeExpr::Array(element) => Type::Array(Box::new(self.resolve_type(element)?)),
+            TypeExpr::Hash(key, value) => Type::Hash(Box::new(self.resolve_type(key)?), Box::new(self.resolve_type(value)?)),
+            TypeExpr::Function(parameters, ret) => {
+                let parameters = parameters.iter().map(|parameter| self.resolve_type(parameter)).collect::<Result<_, _>>()?;
+                Type::Function(parameters, Box::new(self.resolve_type(ret)?))
+            }
+        })
+    }
+
+    // The number of the type parameter `name` names here, if any.
+    fn type_parameter(&self, name: &str) -> Option<usize> {
+        self.type_parameters.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+
+    // The one type all of `types` unify to, or `any` if they do not.
+    fn join(&mut self, types: Vec<Type>) -> Type {
+        let mut types = types.into_iter();
+        let Some(mut joined) = types.next() else {
+            return Type::Any;
+        };
+        for ty in types {
+            if !self.unify(&joined, &ty, None) {
+                joined = Type::Any;
+            }
+        }
+        joined
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.substitution.push(None);
+        self.origins.push(None);
+        self.numeric.push(false);
+        Type::Var(self.substitution.len() - 1)
+    }
+
+    // A fresh variable that is a number if `var` is.
+    fn fresh_copy(&mut self, var: usize) -> Type {
+        let ty = self.fresh();
+        *self.numeric.last_mut().unwrap() = self.numeric[var];
+        ty
+    }
+
+    // `ty` with the variables it starts with followed to what they are bound to.
+    fn shallow(&self, ty: &Type) -> Type {
+        let mut ty = ty;
+        while let Type::Var(var) = ty {
+            match &self.substitution[*var] {
+                Some(bound) => ty = bound,
+                None => break,
+            }
+        }
+        ty.clone()
+    }
+
+    // `ty` with every bound variable in it replaced.
+    fn zonk(&self, ty: &Type) -> Type {
+        match self.shallow(ty) {
+            Type::Array(element) => Type::Array(Box::new(self.zonk(&element))),
+            Type::Hash(key, value) => Type::Hash(Box::new(self.zonk(&key)), Box::new(self.zonk(&value))),
+            Type::Function(parameters, ret) => {
+                Type::Function(parameters.iter().map(|parameter| self.zonk(parameter)).collect(), Box::new(self.zonk(&ret)))
+            }
+            ty => ty,
+        }
+    }
+
+    // Where the unification that decided `ty` happened, if it is a variable
+    // that has been decided.
+    fn origin(&self, ty: &Type) -> Option<Span> {
+        let mut ty = ty;
+        let mut origin = None;
+        while let Type::Var(var) = ty {
+            match &self.substitution[*var] {
+                Some(bound) => {
+                    origin = self.origins[*var].or(origin);
+                    ty = bound;
+                }
+                None => break,
+            }
+        }
+        origin
+    }
+
+    // Makes `a` and `b` the same type, binding variables as needed. If they
+    // cannot be, nothing is bound. `site` is where the unification is
+    // needed, to say later where a variable's type came from.
+    fn unify(&mut self, a: &Type, b: &Type, site: Option<Span>) -> bool {
+        let mark = self.trail.len();
+        let unified = self.unify_types(a, b, site);
+        for var in self.trail.drain(mark..) {
+            if !unified {
+                self.substitution[var] = None;
+                self.origins[var] = None;
+            }
+        }
+        unified
+    }
+
+    fn unify_types(&mut self, a: &Type, b: &Type, site: Option<Span>) -> bool {
+        match (self.shallow(a), self.shallow(b)) {
+            (Type::Var(a), Type::Var(b)) if a == b => true,
+            (Type::Any, _) | (_, Type::Any) => true,
+            // Bound the other way, so that the number keeps its constraint.
+            (Type::Var(a), Type::Var(b)) if self.numeric[a] && !self.numeric[b] => self.unify_types(&Type::Var(b), &Type::Var(a), site),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return false;
+                }
+                if self.numeric[var] && !matches!(ty, Type::Int | Type::Float | Type::Var(_)) {
+                    return false;
+                }
+                self.substitution[var] = Some(ty);
+                self.origins[var] = site;
+                self.trail.push(var);
+                true
+            }
+            (Type::Array(a), Type::Array(b)) => self.unify_types(&a, &b, site),
+            (Type::Hash(a_key, a_value), Type::Hash(b_key, b_value)) => {
+                self.unify_types(&a_key, &b_key, site) && self.unify_types(&a_value, &b_value, site)
+            }
+            (Type::Function(a_parameters, a_ret), Type::Function(b_parameters, b_ret)) => {
+                a_parameters.len() == b_parameters.len()
+                    && a_parameters.iter().zip(&b_parameters).all(|(a, b)| self.unify_types(a, b, site))
+                    && self.unify_types(&a_ret, &b_ret, site)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    // Whether `var` appears in `ty`, which would make binding it infinite.
+    fn occurs(&self, var: usize, ty: &Type) -> bool {
+        let mut vars = vec![];
+        self.free_vars(ty, &mut vars);
+        vars.contains(&var)
+    }
+
+    // The unbound variables in `ty`, in order of appearance.
+    fn free_vars(&self, ty: &Type, vars: &mut Vec<usize>) {
+        match self.shallow(ty) {
+            Type::Var(var) if !vars.contains(&var) => vars.push(var),
+            Type::Array(element) => self.free_vars(&element, vars),
+            Type::Hash(key, value) => {
+                self.free_vars(&key, vars);
+                self.free_vars(&value, vars);
+            }
+            Type::Function(parameters, ret) => {
+                for parameter in &parameters {
+                    self.free_vars(parameter, vars);
+                }
+                self.free_vars(&ret, vars);
+            }
+            _ => {}
+        }
+    }
+
+    // The scheme of a let-bound function: the variables in its type that no
+    // binding in scope mentions can differ between uses.
+    fn generalize(&self, ty: Type) -> Scheme {
+        let mut in_scope = vec![];
+        for binding in self.scopes.iter().flat_map(|scope| scope.values()) {
+            let mut vars = vec![];
+            self.free_vars(&binding.scheme.ty, &mut vars);
+            in_scope.extend(vars.into_iter().filter(|var| !binding.scheme.vars.contains(var)));
+        }
+        for function in &self.returns {
+            self.free_vars(&function.ty, &mut in_scope);
+        }
+
+        let mut vars = vec![];
+        self.free_vars(&ty, &mut vars);
+        vars.retain(|var| !in_scope.contains(var));
+
+        // Type parameters of functions whose bodies have been checked.
+        let mut params = vec![];
+        params_of(&self.zonk(&ty), &mut params);
+        params.retain(|param| !self.type_parameters.iter().any(|scope| scope.values().any(|p| p == param)));
+        Scheme { vars, params, ty }
+    }
+
+    // A copy of the scheme's type with fresh variables for its own. Those
+    // for type parameters are left for the call to check against bounds.
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        if scheme.vars.is_empty() && scheme.params.is_empty() {
+            return scheme.ty.clone();
+        }
+        let fresh: HashMap<usize, Type> = scheme.vars.iter().map(|var| (*var, self.fresh_copy(*var))).collect();
+        let ty = self.substitute(&scheme.ty, &fresh);
+        if scheme.params.is_empty() {
+            return ty;
+        }
+
+        let mut fresh = HashMap::new();
+        for param in &scheme.params {
+            let var = self.fresh();
+            self.pending.push((var.clone(), *param));
+            fresh.insert(*param, var);
+        }
+        replace_params(&self.zonk(&ty), &fresh)
+    }
+
+    fn substitute(&self, ty: &Type, fresh: &HashMap<usize, Type>) -> Type {
+        match ty {
+            Type::Var(var) => match (&self.substitution[*var], fresh.get(var)) {
+                (None, Some(fresh)) => fresh.clone(),
+                // Kept as a variable where possible, so its origin is too.
+                (Some(bound), _) if self.mentions(bound, fresh) => self.substitute(bound, fresh),
+                _ => ty.clone(),
+            },
+            Type::Array(element) => Type::Array(Box::new(self.substitute(element, fresh))),
+            Type::Hash(key, value) => Type::Hash(Box::new(self.substitute(key, fresh)), Box::new(self.substitute(value, fresh))),
+            Type::Function(parameters, ret) => Type::Function(
+                parameters.iter().map(|parameter| self.substitute(parameter, fresh)).collect(),
+                Box::new(self.substitute(ret, fresh)),
+            ),
+            ty => ty.clone(),
+        }
+    }
+
+    fn mentions(&self, ty: &Type, fresh: &HashMap<usize, Type>) -> bool {
+        let mut vars = vec![];
+        self.free_vars(ty, &mut vars);
+        vars.iter().any(|var| fresh.contains_key(var))
+    }
+
+    // `types` as a message shows them: unknown types are named 'a, 'b, ...
+    // in order, and a type some earlier unification decided says where.
+    fn show<const N: usize>(&self, types: [&Type; N], span: Span) -> [String; N] {
+        let mut names = vec![];
+        types.map(|ty| {
+            let shown = match self.shallow(ty) {
+                Type::Var(var) if self.numeric[var] => "int or float".to_string(),
+                ty => rename(&self.zonk(&ty), &mut names).to_string(),
+            };
+            match self.origin(ty) {
+                Some(origin) if origin != span => format!("{} (from {})", shown, origin),
+                _ => shown,
+            }
+        })
+    }
+}
+
+// `ty` with its variables numbered by where they first appear in `names`.
+fn rename(ty: &Type, names: &mut Vec<usize>) -> Type {
+    match ty {
+        Type::Var(var) => {
+            let position = names.iter().position(|name| name == var).unwrap_or_else(|| {
+                names.push(*var);
+                names.len() - 1
+            });
+            Type::Var(position)
+        }
+        Type::Array(element) => Type::Array(Box::new(rename(element, names))),
+        Type::Hash(key, value) => Type::Hash(Box::new(rename(key, names)), Box::new(rename(value, names))),
+        Type::Function(parameters, ret) => {
+            Type::Function(parameters.iter().map(|parameter| rename(parameter, names)).collect(), Box::new(rename(ret, names)))
+        }
+        ty => ty.clone(),
+    }
+}
+
+// The type parameters in `ty`, in order of appearance.
+fn params_of(ty: &Type, params: &mut Vec<usize>) {
+    match ty {
+        Type::Param(param, _) if !params.contains(param) => params.push(*param),
+        Type::Array(element) => params_of(element, params),
+        Type::Hash(key, value) => {
+            params_of(key, params);
+            params_of(value, params);
+        }
+        Type::Function(parameters, ret) => {
+            for parameter in parameters {
+                params_of(parameter, params);
+            }
+            params_of(ret, params);
+        }
+        _ => {}
+    }
+}
+
+// `ty` with the type parameters in `fresh` replaced.
+fn replace_params(ty: &Type, fresh: &HashMap<usize, Type>) -> Type {
+    match ty {
+        Type::Param(param, _) => fresh.get(param).cloned().unwrap_or_else(|| ty.clone()),
+        Type::Array(element) => Type::Array(Box::new(replace_params(element, fresh))),
+        Type::Hash(key, value) => Type::Hash(Box::new(replace_params(key, fresh)), Box::new(replace_params(value, fresh))),
+        Type::Function(parameters, ret) => Type::Function(
+            parameters.iter().map(|parameter| replace_params(parameter, fresh)).collect(),
+            Box::new(replace_params(ret, fresh)),
+        ),
+        ty => ty.clone(),
+    }
+}
+
+// Arrays and hashes are shared and can be filled with anything, so an
+// unannotated binding only keeps that they are an array or a hash.
+fn widen(ty: Type) -> Type {
+    match ty {
+        Type::Array(_) => Type::Array(Box::new(Type::Any)),
+        Type::Hash(..) => Type::Hash(Box::new(Type::Any), Box::new(Type::Any)),
+        ty => ty,
+    }
+}
+
+// The result of an infix operator on two known types, or None where
+// eval_infix_expression raises a TypeError.
+fn operator_type(operator: &str, left: &Type, right: &Type) -> Option<Type> {
+    use Type::*;
+    let comparison = matches!(operator, "<" | ">" | "<=" | ">=");
+    match (left, right) {
+        (Any, _) | (_, Any) if comparison => Some(Bool),
+        (Any, _) | (_, Any) => Some(Any),
+        (Int, Int) => match operator {
+            "+" | "-" | "*" | "/" | "%" | "&" | "|" | "^" | "<<" | ">>" => Some(Int),
+            // A negative exponent gives a float.
+            "**" => Some(Any),
+            _ if comparison => Some(Bool),
+            _ => None,
+        },
+        (Int | Float, Int | Float) => match operator {
+            "+" | "-" | "*" | "/" | "%" | "**" => Some(Float),
+            _ if comparison => Some(Bool),
+            _ => None,
+        },
+        (String, String) => match operator {
+            "+" => Some(String),
+            _ if comparison => Some(Bool),
+            _ => None,
+        },
+        (Bool, Bool) => match operator {
+            "&" | "|" | "^" => Some(Bool),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The names a pattern binds.
+fn pattern_names(pattern: &Pattern, names: &mut Vec<String>) {
+    match pattern {
+        Pattern::Binding(name) | Pattern::Rest(Some(name)) => names.push(name.clone()),
+        Pattern::Array(patterns) | Pattern::Variant { fields: patterns, .. } => {
+            for pattern in patterns {
+                pattern_names(pattern, names);
+            }
+        }
+        Pattern::Hash(pairs) => {
+            for (_, pattern) in pairs {
+                pattern_names(pattern, names);
+            }
+        }
+        Pattern::Wildcard | Pattern::Literal(_) | Pattern::Rest(None) => {}
+    }
+}
@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{display, type_name, Builtin, ErrorKind, Object, OrderedHash, RuntimeError};
+use crate::lexer::Span;
+
+const BUILTINS: &[Builtin] = &[
+    Builtin { name: "keys", func: keys },
+    Builtin { name: "values", func: values },
+    Builtin { name: "error", func: error },
+    Builtin { name: "print", func: print },
+];
+
+// The builtin called `name`, if there is one.
+pub(super) fn lookup(name: &str) -> Option<Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name).copied()
+}
+
+// keys(hash): the keys as an array, in insertion order.
+fn keys(args: Vec<Object>, span: Span) -> Result<Object, RuntimeError> {
+    let hash = expect_hash("keys", &args, span)?;
+    let keys = hash.borrow().iter().map(|(key, _)| key.to_object()).collect();
+    Ok(Object::Array(Rc::new(RefCell::new(keys))))
+}
+
+// values(hash): the values as an array, in insertion order.
+fn values(args: Vec<Object>, span: Span) -> Result<Object, RuntimeError> {
+    let hash = expect_hash("values", &args, span)?;
+    let values = hash.borrow().iter().map(|(_, value)| value.clone()).collect();
+    Ok(Object::Array(Rc::new(RefCell::new(values))))
+}
+
+// error(message) or error(kind, message): an error value, ready to `throw`.
+fn error(args: Vec<Object>, span: Span) -> Result<Object, RuntimeError> {
+    let (kind, message) = match args.as_slice() {
+        [Object::String(message)] => (ErrorKind::Custom("Error".to_string()), message),
+        [Object::String(kind), Object::String(message)] => (ErrorKind::from_name(kind), message),
+        [_] | [_, _] => {
+            return Err(RuntimeError::new(ErrorKind::Type, "error() expects string arguments", Some(span)));
+        }
+        _ => {
+            return Err(RuntimeError::new(
+                ErrorKind::Type,
+                format!("error() expects 1 or 2 arguments but got {}", args.len()),
+                Some(span),
+            ))
+        }
+    };
+    Ok(Object::Error(Box::new(RuntimeError::new(kind, message.clone(), Some(span)))))
+}
+
+// print(values...): writes the values, space-separated, on one line.
+fn print(args: Vec<Object>, span: Span) -> Result<Object, RuntimeError> {
+    let shown = args.iter().map(|arg| display(arg, Some(span))).collect::<Result<Vec<_>, _>>()?;
+    println!("{}", shown.join(" "));
+    Ok(Object::Null)
+}
+
+fn expect_hash<'a>(name: &str, args: &'a [Object], span: Span) -> Result<&'a Rc<RefCell<OrderedHash>>, RuntimeError> {
+    match args {
+        [Object::Hash(hash)] => Ok(hash),
+        [other] => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("{}() expects a hash, not {}", name, type_name(other)),
+            Some(span),
+        )),
+        _ => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("{}() expects 1 argument but got {}", name, args.len()),
+            Some(span),
+        )),
+    }
+}
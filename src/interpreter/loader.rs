@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::{eval_program, Environment, ErrorKind, Frame, Object, RuntimeError};
+use crate::checker::{check_program, Severity};
+use crate::lexer::{Lexer, Span};
+use crate::parser::Parser;
+
+// An evaluated file: the values of the names it exports, in the order its
+// `export`s ran. A module is only equal to itself.
+pub struct Module {
+    // The path it was first imported by, as written there.
+    pub name: String,
+    pub exports: Vec<(String, Object)>,
+}
+
+impl Module {
+    pub fn get(&self, name: &str) -> Option<&Object> {
+        self.exports.iter().find(|(export, _)| export == name).map(|(_, value)| value)
+    }
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// Skips the values, which can contain functions that close over the module.
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+// Shared by every environment of one program, so that each file is
+// evaluated once however many files import it.
+#[derive(Default)]
+pub(super) struct Loader {
+    modules: RefCell<HashMap<PathBuf, Rc<Module>>>,
+    // Files whose evaluation has started but not finished, outermost first,
+    // with the paths they were imported by.
+    loading: RefCell<Vec<(PathBuf, String)>>,
+}
+
+impl Loader {
+    // `import "<path>"` from the file `env` belongs to.
+    pub(super) fn import(&self, path: &str, env: &Environment, span: Span) -> Result<Rc<Module>, RuntimeError> {
+        let file = fs::canonicalize(env.module.dir.join(path))
+            .map_err(|err| RuntimeError::new(ErrorKind::Import, format!("cannot import \"{}\": {}", path, err), Some(span)))?;
+
+        if let Some(module) = self.modules.borrow().get(&file) {
+            return Ok(module.clone());
+        }
+
+        let loading = self.loading.borrow();
+        if let Some(start) = loading.iter().position(|(loading, _)| *loading == file) {
+            let mut cycle: Vec<&str> = loading[start..].iter().map(|(_, name)| name.as_str()).collect();
+            cycle.push(path);
+            return Err(RuntimeError::new(ErrorKind::Import, format!("import cycle: {}", cycle.join(" -> ")), Some(span)));
+        }
+        drop(loading);
+
+        // An error inside the module keeps its own location; the import
+        // shows up in its stack like a call would.
+        let (module, _) = self.run(file.clone(), path.to_string(), &env.root()).map_err(|mut err| {
+            err.stack.push(Frame { function: format!("import {:?}", path), span });
+            err
+        })?;
+        self.modules.borrow_mut().insert(file, module.clone());
+        Ok(module)
+    }
+
+    // Evaluates `file` in its own global scope under `prelude`, giving the
+    // module and the value of its last statement.
+    pub(super) fn run(&self, file: PathBuf, name: String, prelude: &Environment) -> Result<(Rc<Module>, Object), RuntimeError> {
+        let source = fs::read_to_string(&file).map_err(|err| RuntimeError::new(ErrorKind::Import, err.to_string(), None))?;
+
+        let mut parser = Parser::new(Lexer::new(&source));
+        let program = parser.parse_program();
+        // In the order they appear in the file, whichever stage found them.
+        let lexer_errors = parser.lexer_errors().iter().map(|err| (err.message.clone(), err.span));
+        let parse_errors = parser.errors().iter().map(|err| (err.message.clone(), err.span));
+        let mut syntax_errors: Vec<(String, Span)> = lexer_errors.chain(parse_errors).collect();
+        syntax_errors.sort_by_key(|(_, span)| span.start);
+        if let Some(err) = file_error(ErrorKind::Syntax, syntax_errors.into_iter()) {
+            return Err(err);
+        }
+        // Warnings are left to the run to confirm.
+        let (type_errors, warnings): (Vec<_>, Vec<_>) =
+            check_program(&program).into_iter().partition(|err| err.severity == Severity::Error);
+        if let Some(err) = file_error(ErrorKind::Type, type_errors.into_iter().map(|err| (err.message, err.span))) {
+            return Err(err);
+        }
+        for warning in warnings {
+            eprintln!("warning: {} in {}", warning, name);
+        }
+
+        let dir = file.parent().map_or_else(PathBuf::new, Path::to_path_buf);
+        let mut env = prelude.module_scope(dir);
+
+        self.loading.borrow_mut().push((file, name.clone()));
+        let result = eval_program(program, &mut env);
+        self.loading.borrow_mut().pop();
+
+        if let Object::Error(err) = result {
+            return Err(*err);
+        }
+        let module = Module { name, exports: env.exports() };
+        Ok((Rc::new(module), result))
+    }
+}
+
+// One error for everything found wrong with a file before running it: the
+// first problem, followed by the rest.
+fn file_error(kind: ErrorKind, errors: impl DoubleEndedIterator<Item = (String, Span)>) -> Option<RuntimeError> {
+    errors.rev().fold(None, |next, (message, span)| {
+        let mut err = RuntimeError::new(kind.clone(), message, Some(span));
+        err.next = next.map(Box::new);
+        Some(err)
+    })
+}
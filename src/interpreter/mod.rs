@@ -1,43 +1,823 @@
-use std::collections::HashMap;
+mod builtins;
+mod loader;
 
-use crate::ast::{Expression, Statement};
+pub use loader::Module;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::{
+    Block, Catch, Expression, InterpolationPart, MatchArm, Method, Pattern, Statement, TraitMethod, TypeAnnotation, TypeExpr,
+    TypeParameter, Variant,
+};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::lexer::Span;
+use loader::Loader;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Object {
     Integer(i64),
+    Float(f64),
     Boolean(bool),
+    String(String),
+    // Arrays are shared: every copy of the value sees the same elements.
+    Array(Rc<RefCell<Vec<Object>>>),
+    // Hashes are shared the same way.
+    Hash(Rc<RefCell<OrderedHash>>),
+    // The type declared by `struct Point { ... }`, and values built from it.
+    StructType(Rc<StructType>),
+    // Instances are shared like arrays: copies see the same fields.
+    Instance(Rc<RefCell<Instance>>),
+    // The type declared by `enum Shape { ... }`, its values, and the
+    // function that builds a variant with a payload, such as Shape::Circle.
+    EnumType(Rc<EnumType>),
+    Variant(Rc<VariantValue>),
+    Constructor(Rc<EnumType>, usize),
+    // The trait declared by `trait Show { ... }`.
+    Trait(Rc<Trait>),
+    Function(Rc<Function>),
+    Builtin(Builtin),
+    // What `import "lib/math.aion" as math` binds to `math`.
+    Module(Rc<Module>),
+    Error(Box<RuntimeError>),
     Null,
 }
 
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Object::Integer(i) => write!(f, "{}", i),
-            Object::Boolean(b) => write!(f, "{}", b),
-            Object::Null => write!(f, "null"),
+        write_object(f, self, &mut vec![])
+    }
+}
+
+// Writes `obj`. `showing` holds the arrays, hashes and instances being
+// written further out, so that one containing itself is written as `[...]`,
+// `{...}` or `Name {...}` where it recurs.
+fn write_object(f: &mut fmt::Formatter<'_>, obj: &Object, showing: &mut Vec<*const ()>) -> fmt::Result {
+    match obj {
+        Object::Integer(i) => write!(f, "{}", i),
+        Object::Float(x) => write!(f, "{:?}", x),
+        Object::Boolean(b) => write!(f, "{}", b),
+        Object::String(s) => write!(f, "{}", s),
+        Object::Array(elements) => {
+            let ptr = Rc::as_ptr(elements) as *const ();
+            if showing.contains(&ptr) {
+                return write!(f, "[...]");
+            }
+            showing.push(ptr);
+            write!(f, "[")?;
+            for (i, element) in elements.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_element(f, element, showing)?;
+            }
+            showing.pop();
+            write!(f, "]")
+        }
+        Object::Hash(hash) => {
+            let ptr = Rc::as_ptr(hash) as *const ();
+            if showing.contains(&ptr) {
+                return write!(f, "{{...}}");
+            }
+            showing.push(ptr);
+            write!(f, "{{")?;
+            for (i, (key, value)) in hash.borrow().iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}: ", key)?;
+                write_element(f, value, showing)?;
+            }
+            showing.pop();
+            write!(f, "}}")
+        }
+        Object::StructType(ty) => write!(f, "struct {}", ty.name),
+        Object::Instance(instance) => {
+            let ptr = Rc::as_ptr(instance) as *const ();
+            let instance = instance.borrow();
+            if showing.contains(&ptr) {
+                return write!(f, "{} {{...}}", instance.ty.name);
+            }
+            showing.push(ptr);
+            write!(f, "{} {{", instance.ty.name)?;
+            for (i, (name, value)) in instance.ty.fields.iter().zip(&instance.fields).enumerate() {
+                write!(f, "{}{}: ", if i > 0 { ", " } else { " " }, name)?;
+                write_element(f, value, showing)?;
+            }
+            showing.pop();
+            write!(f, " }}")
+        }
+        Object::EnumType(ty) => write!(f, "enum {}", ty.name),
+        Object::Variant(value) => {
+            write!(f, "{}::{}", value.ty.name, value.ty.variants[value.variant].name)?;
+            if value.values.is_empty() {
+                return Ok(());
+            }
+            write!(f, "(")?;
+            for (i, field) in value.values.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_element(f, field, showing)?;
+            }
+            write!(f, ")")
+        }
+        Object::Constructor(ty, variant) => {
+            let variant = &ty.variants[*variant];
+            write!(f, "fn {}::{}({})", ty.name, variant.name, variant.fields.join(", "))
+        }
+        Object::Trait(tr) => write!(f, "trait {}", tr.name),
+        Object::Function(func) => write!(f, "fn({})", func.parameters.join(", ")),
+        Object::Builtin(builtin) => write!(f, "builtin {}", builtin.name),
+        Object::Module(module) => write!(f, "module {:?}", module.name),
+        Object::Error(err) => write!(f, "{}: {}", err.kind, err),
+        Object::Null => write!(f, "null"),
+    }
+}
+
+// Writes a value held by another one, where strings are quoted.
+fn write_element(f: &mut fmt::Formatter<'_>, obj: &Object, showing: &mut Vec<*const ()>) -> fmt::Result {
+    match obj {
+        Object::String(s) => write!(f, "{:?}", s),
+        other => write_object(f, other, showing),
+    }
+}
+
+// Values are equal by content, except functions and types, which are only
+// equal to themselves. Types that implement Eq decide for themselves; an
+// `eq` method that fails counts as unequal here.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        equal(self, other, None, &mut vec![]).unwrap_or(false)
+    }
+}
+
+// Whether `left == right`, the way scripts see it: through `left`'s `eq`
+// method if its type implements Eq, otherwise by content, with the values
+// inside arrays, hashes, instances and variants compared the same way.
+// `span` is where the comparison was asked for.
+//
+// `comparing` holds the pairs of arrays, hashes and instances being compared
+// further out. Meeting a pair again means both sides cycle back the same way,
+// and nothing along the way has differed.
+fn equal(left: &Object, right: &Object, span: Option<Span>, comparing: &mut Vec<(*const (), *const ())>) -> Result<bool, RuntimeError> {
+    use Object::*;
+    if let Some(eq) = builtin_method(left, "Eq", "eq") {
+        return Ok(is_truthy(&call_method(eq, "eq", vec![left.clone(), right.clone()], span)?));
+    }
+
+    let pair = match (left, right) {
+        (Array(l), Array(r)) => Some((Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ())),
+        (Hash(l), Hash(r)) => Some((Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ())),
+        (Instance(l), Instance(r)) => Some((Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ())),
+        _ => None,
+    };
+    if let Some(pair) = pair {
+        if comparing.contains(&pair) {
+            return Ok(true);
         }
+        comparing.push(pair);
+        let result = equal_contents(left, right, span, comparing);
+        comparing.pop();
+        return result;
     }
+
+    Ok(match (left, right) {
+        (Integer(l), Integer(r)) => l == r,
+        (Float(l), Float(r)) => l == r,
+        (Boolean(l), Boolean(r)) => l == r,
+        (String(l), String(r)) => l == r,
+        (StructType(l), StructType(r)) => l == r,
+        (EnumType(l), EnumType(r)) => l == r,
+        (Variant(l), Variant(r)) => l.ty == r.ty && l.variant == r.variant && all_equal(&l.values, &r.values, span, comparing)?,
+        (Constructor(l, i), Constructor(r, j)) => l == r && i == j,
+        (Trait(l), Trait(r)) => l == r,
+        (Function(l), Function(r)) => l == r,
+        (Builtin(l), Builtin(r)) => l == r,
+        (Module(l), Module(r)) => l == r,
+        (Error(l), Error(r)) => l == r,
+        (Null, Null) => true,
+        _ => false,
+    })
+}
+
+// Compares two arrays, hashes or instances element by element.
+fn equal_contents(left: &Object, right: &Object, span: Option<Span>, comparing: &mut Vec<(*const (), *const ())>) -> Result<bool, RuntimeError> {
+    match (left, right) {
+        (Object::Array(l), Object::Array(r)) => {
+            let (l, r) = (l.borrow(), r.borrow());
+            Ok(l.len() == r.len() && all_equal(&l, &r, span, comparing)?)
+        }
+        (Object::Hash(l), Object::Hash(r)) => {
+            let (l, r) = (l.borrow(), r.borrow());
+            if l.len() != r.len() {
+                return Ok(false);
+            }
+            for (key, l) in l.iter() {
+                match r.get(key) {
+                    Some(r) if equal(l, r, span, comparing)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            Ok(true)
+        }
+        (Object::Instance(l), Object::Instance(r)) => {
+            let (l, r) = (l.borrow(), r.borrow());
+            Ok(l.ty == r.ty && all_equal(&l.fields, &r.fields, span, comparing)?)
+        }
+        _ => Ok(false),
+    }
+}
+
+// Whether the values of two equally long lists are pairwise equal.
+fn all_equal(left: &[Object], right: &[Object], span: Option<Span>, comparing: &mut Vec<(*const (), *const ())>) -> Result<bool, RuntimeError> {
+    for (l, r) in left.iter().zip(right) {
+        if !equal(l, r, span, comparing)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// The values that can be used as hash keys. Floats are left out because
+// NaN is not equal to itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+    // A value whose type implements Hash.
+    Custom(Rc<CustomKey>),
 }
 
+// A value used as a key through its `hash` method: what `hash` returned
+// only picks the bucket, and two keys in one bucket are the same key if the
+// values are equal, through Eq where the type implements it. Iterating the
+// hash gives back the value itself.
 #[derive(Debug)]
+pub struct CustomKey {
+    pub key: HashKey,
+    pub value: Object,
+}
+
+impl PartialEq for CustomKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.value == other.value
+    }
+}
+
+impl Eq for CustomKey {}
+
+impl std::hash::Hash for CustomKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+impl HashKey {
+    pub fn from_object(obj: &Object) -> Option<HashKey> {
+        match obj {
+            Object::Integer(i) => Some(HashKey::Integer(*i)),
+            Object::Boolean(b) => Some(HashKey::Boolean(*b)),
+            Object::String(s) => Some(HashKey::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Object {
+        match self {
+            HashKey::Integer(i) => Object::Integer(*i),
+            HashKey::Boolean(b) => Object::Boolean(*b),
+            HashKey::String(s) => Object::String(s.clone()),
+            HashKey::Custom(custom) => custom.value.clone(),
+        }
+    }
+}
+
+impl fmt::Display for HashKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashKey::Integer(i) => write!(f, "{}", i),
+            HashKey::Boolean(b) => write!(f, "{}", b),
+            HashKey::String(s) => write!(f, "{:?}", s),
+            HashKey::Custom(custom) => write!(f, "{}", custom.value),
+        }
+    }
+}
+
+// A hash map that remembers insertion order, so that printing, `keys()`,
+// `values()` and `for` loops all see entries in the order they were added.
+// Overwriting a key keeps its original position.
+#[derive(Debug, Clone, Default)]
+pub struct OrderedHash {
+    entries: Vec<(HashKey, Object)>,
+    positions: HashMap<HashKey, usize>,
+}
+
+impl OrderedHash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&Object> {
+        self.positions.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn insert(&mut self, key: HashKey, value: Object) {
+        match self.positions.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&HashKey, &Object)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+// Two hashes are equal when they hold the same pairs, in any order.
+impl PartialEq for OrderedHash {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+// The methods `impl` blocks have added to a struct or enum type.
+type Methods = RefCell<HashMap<String, Rc<Function>>>;
+
+// The traits `impl Trait for Type` blocks have implemented for a type.
+type Traits = RefCell<Vec<Rc<Trait>>>;
+
+// A type declared with `struct`. `impl` blocks add its methods later.
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+    methods: Methods,
+    traits: Traits,
+}
+
+impl StructType {
+    fn new(name: &str, fields: &[String]) -> Self {
+        StructType {
+            name: name.to_string(),
+            fields: fields.to_vec(),
+            methods: RefCell::new(HashMap::new()),
+            traits: RefCell::new(vec![]),
+        }
+    }
+
+    fn field_position(&self, field: &str) -> Option<usize> {
+        self.fields.iter().position(|name| name == field)
+    }
+
+    fn method(&self, name: &str) -> Option<Rc<Function>> {
+        self.methods.borrow().get(name).cloned()
+    }
+}
+
+// Each declaration is its own type, even if another has the same fields.
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// Skips `methods`, whose environments can contain this very type.
+impl fmt::Debug for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructType")
+            .field("name", &self.name)
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+// A type declared with `enum`. Like a struct type, it is only equal to
+// itself, and `impl` blocks can add methods.
+pub struct EnumType {
+    pub name: String,
+    pub variants: Vec<Variant>,
+    methods: Methods,
+    traits: Traits,
+}
+
+impl EnumType {
+    fn new(name: &str, variants: &[Variant]) -> Self {
+        EnumType {
+            name: name.to_string(),
+            variants: variants.to_vec(),
+            methods: RefCell::new(HashMap::new()),
+            traits: RefCell::new(vec![]),
+        }
+    }
+
+    fn variant_position(&self, name: &str) -> Option<usize> {
+        self.variants.iter().position(|variant| variant.name == name)
+    }
+}
+
+impl PartialEq for EnumType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// Skips `methods`, whose environments can contain this very type.
+impl fmt::Debug for EnumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnumType")
+            .field("name", &self.name)
+            .field("variants", &self.variants)
+            .finish_non_exhaustive()
+    }
+}
+
+// A trait declared with `trait`: the methods its impls must have, and the
+// default bodies of those they may leave out.
+pub struct Trait {
+    pub name: String,
+    methods: Vec<TraitMethod>,
+    // Where the default bodies were declared.
+    env: Environment,
+    // Declared by the prelude, like Eq and Display, which operators and
+    // builtins look for on user types.
+    builtin: bool,
+}
+
+impl Trait {
+    fn new(name: &str, methods: &[TraitMethod], env: &Environment) -> Self {
+        // Only the prelude's code runs in the outermost scope.
+        let builtin = env.scope.outer.is_none();
+        Trait { name: name.to_string(), methods: methods.to_vec(), env: env.clone(), builtin }
+    }
+}
+
+// Each declaration is its own trait, even if another has the same methods.
+impl PartialEq for Trait {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// Skips `env`, which can contain this very trait.
+impl fmt::Debug for Trait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Trait").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+// A value of an enum type: which variant it is, and that variant's payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantValue {
+    pub ty: Rc<EnumType>,
+    pub variant: usize,
+    pub values: Vec<Object>,
+}
+
+// A value of a struct type, with one value per declared field, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub ty: Rc<StructType>,
+    pub fields: Vec<Object>,
+}
+
+// A function value: its code plus the environment it was defined in.
+pub struct Function {
+    pub parameters: Vec<String>,
+    pub body: Block,
+    pub env: Environment,
+    // The traits arguments must implement, by parameter position, for
+    // parameters typed with a bounded type parameter like `T: Show`.
+    pub bounds: Vec<(usize, Rc<Trait>)>,
+}
+
+// Functions are equal only to themselves.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// Skips `env`, which can contain this very function.
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("parameters", &self.parameters)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+// A function implemented in Rust, such as `keys`.
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    func: fn(Vec<Object>, Span) -> Result<Object, RuntimeError>,
+}
+
+// Builtins are identified by name.
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Builtin({})", self.name)
+    }
+}
+
+// Something that went wrong while evaluating, such as calling a non-function.
+// Scripts can `throw` and `catch` these; a caught one is an Object::Error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub span: Option<Span>,
+    // The calls the error unwound through, innermost first.
+    pub stack: Vec<Frame>,
+    // The next of several errors found together, such as a file's next
+    // syntax error. It is shown after this one.
+    pub next: Option<Box<RuntimeError>>,
+}
+
+impl RuntimeError {
+    fn new(kind: ErrorKind, message: impl Into<String>, span: Option<Span>) -> Self {
+        RuntimeError { kind, message: message.into(), span, stack: vec![], next: None }
+    }
+
+    fn at(self, span: Span) -> Self {
+        RuntimeError { span: Some(span), ..self }
+    }
+
+    // The stack as it is printed, innermost first. A run of the same frame,
+    // as deep recursion leaves, is shown once.
+    pub fn trace(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for run in self.stack.chunk_by(|a, b| a == b) {
+            match run.len() {
+                1 => lines.push(run[0].to_string()),
+                n => lines.push(format!("{} (repeated {} times)", run[0], n)),
+            }
+        }
+        lines
+    }
+}
+
+// What went wrong, so that a `catch` can tell failures apart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    // A value of the wrong type: bad operands, calling a non-function, ...
+    Type,
+    // Assigning to an undeclared or const name, or naming a missing variant.
+    Name,
+    // An index or slice outside of an array or string.
+    Index,
+    // A hash key that is not there.
+    Key,
+    // A struct field, method or module export that is not there.
+    Field,
+    // Division by zero and integer overflow.
+    Arithmetic,
+    // A value that no match arm or let pattern accepts.
+    Match,
+    // A file that cannot be imported.
+    Import,
+    // A file that does not lex or parse.
+    Syntax,
+    // Calls nested deeper than MAX_CALL_DEPTH.
+    Recursion,
+    // Thrown by a script, with a kind it chose ("Error" by default).
+    Custom(String),
+}
+
+impl ErrorKind {
+    // The kind scripts see as `err.kind`, or pass to error(kind, message).
+    pub fn from_name(name: &str) -> ErrorKind {
+        match name {
+            "TypeError" => ErrorKind::Type,
+            "NameError" => ErrorKind::Name,
+            "IndexError" => ErrorKind::Index,
+            "KeyError" => ErrorKind::Key,
+            "FieldError" => ErrorKind::Field,
+            "ArithmeticError" => ErrorKind::Arithmetic,
+            "MatchError" => ErrorKind::Match,
+            "ImportError" => ErrorKind::Import,
+            "SyntaxError" => ErrorKind::Syntax,
+            "RecursionError" => ErrorKind::Recursion,
+            other => ErrorKind::Custom(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorKind::Type => "TypeError",
+            ErrorKind::Name => "NameError",
+            ErrorKind::Index => "IndexError",
+            ErrorKind::Key => "KeyError",
+            ErrorKind::Field => "FieldError",
+            ErrorKind::Arithmetic => "ArithmeticError",
+            ErrorKind::Match => "MatchError",
+            ErrorKind::Import => "ImportError",
+            ErrorKind::Syntax => "SyntaxError",
+            ErrorKind::Recursion => "RecursionError",
+            ErrorKind::Custom(name) => name,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A function call an error passed through: who was called, and from where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub span: Span,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.function, self.span)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.message, span)?,
+            None => write!(f, "{}", self.message)?,
+        }
+        match &self.next {
+            Some(next) => write!(f, "\n{}", next),
+            None => Ok(()),
+        }
+    }
+}
+
+// A cheap-to-clone handle to one scope: clones share the same bindings,
+// which is how a function keeps seeing the environment it was defined in.
+// Lookups that miss walk out through the enclosing scopes.
+#[derive(Debug, Clone)]
 pub struct Environment {
-    pub store: HashMap<String, Object>,
+    scope: Rc<Scope>,
+    // The file this environment's code comes from. Every scope of a file
+    // shares it.
+    module: Rc<ModuleContext>,
+}
+
+struct ModuleContext {
+    // Where the file's imports are resolved from.
+    dir: PathBuf,
+    // The names the file has exported so far.
+    exports: RefCell<Vec<String>>,
+    loader: Rc<Loader>,
+}
+
+// Skips the loader, which holds every module of the program.
+impl fmt::Debug for ModuleContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModuleContext").field("dir", &self.dir).finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct Scope {
+    store: RefCell<HashMap<String, Object>>,
+    // Names in `store` that were bound with `const`.
+    consts: RefCell<HashSet<String>>,
+    outer: Option<Environment>,
+}
+
+// Why `Environment::assign` refused a write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignError {
+    Undeclared,
+    Const,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+// Declarations every program can use, such as Option and Result.
+pub(crate) const PRELUDE: &str = include_str!("prelude.aion");
+
 impl Environment {
+    // A global scope. Its parent holds the prelude, so programs can shadow
+    // prelude names without touching them. Imports are resolved from the
+    // current directory.
     pub fn new() -> Self {
+        let module = ModuleContext {
+            dir: PathBuf::new(),
+            exports: RefCell::new(vec![]),
+            loader: Rc::new(Loader::default()),
+        };
+        let mut prelude = Environment {
+            scope: Rc::new(Scope {
+                store: RefCell::new(HashMap::new()),
+                consts: RefCell::new(HashSet::new()),
+                outer: None,
+            }),
+            module: Rc::new(module),
+        };
+        let program = Parser::new(Lexer::new(PRELUDE)).parse_program();
+        eval_program(program, &mut prelude);
+        prelude.enclosed()
+    }
+
+    // A new, empty scope whose lookups fall back to `self`.
+    pub fn enclosed(&self) -> Environment {
         Environment {
-            store: HashMap::new(),
+            scope: Rc::new(Scope {
+                store: RefCell::new(HashMap::new()),
+                consts: RefCell::new(HashSet::new()),
+                outer: Some(self.clone()),
+            }),
+            module: self.module.clone(),
+        }
+    }
+
+    // The global scope of a file in `dir`, under `self` (the prelude).
+    fn module_scope(&self, dir: PathBuf) -> Environment {
+        let module = ModuleContext {
+            dir,
+            exports: RefCell::new(vec![]),
+            loader: self.module.loader.clone(),
+        };
+        Environment { module: Rc::new(module), ..self.enclosed() }
+    }
+
+    // The outermost scope, which holds the prelude.
+    fn root(&self) -> Environment {
+        match &self.scope.outer {
+            Some(outer) => outer.root(),
+            None => self.clone(),
         }
     }
 
+    // The current values of the names this file has exported.
+    fn exports(&self) -> Vec<(String, Object)> {
+        let names = self.module.exports.borrow();
+        names.iter().filter_map(|name| Some((name.clone(), self.get(name)?))).collect()
+    }
+
     pub fn get(&self, name: &str) -> Option<Object> {
-        self.store.get(name).cloned()
+        if let Some(val) = self.scope.store.borrow().get(name) {
+            return Some(val.clone());
+        }
+        self.scope.outer.as_ref()?.get(name)
+    }
+
+    // `let`: binds `name` in this scope, shadowing any outer binding.
+    pub fn define(&self, name: String, val: Object) {
+        self.scope.consts.borrow_mut().remove(&name);
+        self.scope.store.borrow_mut().insert(name, val);
+    }
+
+    // `const`: like `define`, but later assignments to `name` are refused.
+    pub fn define_const(&self, name: String, val: Object) {
+        self.scope.consts.borrow_mut().insert(name.clone());
+        self.scope.store.borrow_mut().insert(name, val);
+    }
+
+    // Whether `name` is a const of this scope itself.
+    pub fn is_const(&self, name: &str) -> bool {
+        self.scope.consts.borrow().contains(name)
     }
 
-    pub fn set(&mut self, name: String, val: Object) {
-        self.store.insert(name, val);
+    // Assignment: updates the nearest scope that already binds `name`,
+    // unless that binding is a const. Nothing changes on an error.
+    pub fn assign(&self, name: &str, val: Object) -> Result<(), AssignError> {
+        if let Some(slot) = self.scope.store.borrow_mut().get_mut(name) {
+            if self.scope.consts.borrow().contains(name) {
+                return Err(AssignError::Const);
+            }
+            *slot = val;
+            return Ok(());
+        }
+        match &self.scope.outer {
+            Some(outer) => outer.assign(name, val),
+            None => Err(AssignError::Undeclared),
+        }
     }
 }
 
@@ -45,106 +825,1435 @@ impl Environment {
 enum EvalResult {
     Value(Object),
     Return(Object),
+    // Unwind to the nearest loop, which stops with this value.
+    Break(Object),
+    // Unwind to the nearest loop, which starts its next iteration.
+    Continue,
+    Error(Box<RuntimeError>),
 }
 
 use EvalResult::{Return, Value};
 
+// Unwraps a Value, or hands any other signal (e.g. a `return`) straight
+// back to the caller so it can unwind to where it is handled.
+macro_rules! value {
+    ($result:expr) => {
+        match $result {
+            Value(obj) => obj,
+            signal => return signal,
+        }
+    };
+}
+
+// Unwraps an Ok, or turns an Err into an error signal.
+macro_rules! ok {
+    ($result:expr) => {
+        match $result {
+            Ok(obj) => obj,
+            Err(err) => return EvalResult::Error(Box::new(err)),
+        }
+    };
+}
+
+// Runs the file at `path` as a program of its own, resolving its imports
+// relative to it.
+pub fn run_file(path: &Path) -> Object {
+    let env = Environment::new();
+    let file = match std::fs::canonicalize(path) {
+        Ok(file) => file,
+        Err(err) => return Object::Error(Box::new(RuntimeError::new(ErrorKind::Import, format!("cannot run {}: {}", path.display(), err), None))),
+    };
+    match env.module.loader.run(file, path.display().to_string(), &env.root()) {
+        Ok((_, result)) => result,
+        Err(err) => Object::Error(Box::new(err)),
+    }
+}
+
 pub fn eval_program(statements: Vec<Statement>, env: &mut Environment) -> Object {
     let mut result = Object::Null;
 
-    for stmt in statements {
+    for stmt in &statements {
         match eval_statement(stmt, env) {
             Return(obj) => return obj,
             Value(obj) => result = obj,
+            EvalResult::Error(err) => return Object::Error(err),
+            // The parser rejects these outside of a loop.
+            EvalResult::Break(_) | EvalResult::Continue => {
+                return Object::Error(Box::new(RuntimeError::new(ErrorKind::Syntax, "'break' or 'continue' outside of a loop", None)))
+            }
         }
     }
 
     result
 }
 
-fn eval_statement(stmt: Statement, env: &mut Environment) -> EvalResult {
+// A block's value is the value of its last statement. A `return` stops the
+// block and keeps unwinding.
+fn eval_block(statements: &Block, env: &mut Environment) -> EvalResult {
+    let mut result = Object::Null;
+
+    for stmt in statements {
+        result = value!(eval_statement(stmt, env));
+    }
+
+    Value(result)
+}
+
+// The tree is evaluated by reference, and each compound statement and
+// expression by a function of its own, so that a recursive call only holds
+// small stack frames for the code it is in.
+fn eval_statement(stmt: &Statement, env: &mut Environment) -> EvalResult {
     match stmt {
-        Statement::LetStatement { name, value } => {
-            let val = eval_expression(value, env);
-            env.set(name, val.clone());
+        Statement::LetStatement { name, value, span, .. } => {
+            ok!(check_redeclaration(name, *span, env));
+            let val = value!(eval_expression(value, env));
+            env.define(name.clone(), val);
+            Value(Object::Null)
+        }
+        Statement::LetPattern { pattern, value, span } => eval_let_pattern(pattern, value, *span, env),
+        Statement::ConstStatement { name, value, span, .. } => {
+            ok!(check_redeclaration(name, *span, env));
+            let val = value!(eval_expression(value, env));
+            env.define_const(name.clone(), val);
             Value(Object::Null)
         }
         Statement::ReturnStatement { value } => {
-            let val = eval_expression(value, env);
+            let val = value!(eval_expression(value, env));
             Return(val)
         }
-        Statement::ExpressionStatement(expr) => {
-            let val = eval_expression(expr, env);
-            Value(val)
+        Statement::ExpressionStatement(expr) => eval_expression(expr, env),
+        Statement::Block(statements) => eval_block(statements, &mut env.enclosed()),
+        Statement::StructDeclaration { .. } | Statement::EnumDeclaration { .. } | Statement::Trait { .. } => {
+            eval_declaration(stmt, env);
+            Value(Object::Null)
         }
+        Statement::Throw { value, span } => eval_throw(value, *span, env),
+        Statement::Import { path, alias, span } => eval_import(path, alias, *span, env),
+        Statement::Export(statement) => eval_export(statement, env),
+        Statement::Impl { type_name, trait_name, methods, span } => eval_impl(type_name, trait_name.as_deref(), methods, *span, env),
+        Statement::Break { value } => match value {
+            Some(value) => EvalResult::Break(value!(eval_expression(value, env))),
+            None => EvalResult::Break(Object::Null),
+        },
+        Statement::Continue => EvalResult::Continue,
     }
 }
 
-fn eval_expression(expr: Expression, env: &mut Environment) -> Object {
+// Defines the struct, enum or trait `stmt` declares.
+fn eval_declaration(stmt: &Statement, env: &mut Environment) {
+    let (name, declared) = match stmt {
+        Statement::StructDeclaration { name, fields } => (name, Object::StructType(Rc::new(StructType::new(name, fields)))),
+        Statement::EnumDeclaration { name, variants } => (name, Object::EnumType(Rc::new(EnumType::new(name, variants)))),
+        Statement::Trait { name, methods, .. } => (name, Object::Trait(Rc::new(Trait::new(name, methods, env)))),
+        _ => return,
+    };
+    env.define(name.clone(), declared);
+}
+
+fn eval_import(path: &str, alias: &str, span: Span, env: &mut Environment) -> EvalResult {
+    let module = ok!(env.module.loader.clone().import(path, env, span));
+    env.define(alias.to_string(), Object::Module(module));
+    Value(Object::Null)
+}
+
+fn eval_let_pattern(pattern: &Pattern, value: &Expression, span: Span, env: &mut Environment) -> EvalResult {
+    let val = value!(eval_expression(value, env));
+    let mut bindings = vec![];
+    if !ok!(match_pattern(pattern, &val, env, &mut bindings)) {
+        return EvalResult::Error(Box::new(RuntimeError::new(
+            ErrorKind::Match,
+            format!("{} does not match the pattern in this let", val),
+            Some(span),
+        )));
+    }
+    for (name, _) in &bindings {
+        ok!(check_redeclaration(name, span, env));
+    }
+    for (name, val) in bindings {
+        env.define(name, val);
+    }
+    Value(Object::Null)
+}
+
+// A const cannot be replaced by another declaration in its own scope.
+// Inner scopes may still shadow it.
+fn check_redeclaration(name: &str, span: Span, env: &Environment) -> Result<(), RuntimeError> {
+    if env.is_const(name) {
+        return Err(RuntimeError::new(ErrorKind::Name, format!("cannot redeclare const '{}'", name), Some(span)));
+    }
+    Ok(())
+}
+
+// A thrown string becomes an error; a caught error is rethrown as is.
+fn eval_throw(value: &Expression, span: Span, env: &mut Environment) -> EvalResult {
+    let err = match value!(eval_expression(value, env)) {
+        Object::Error(err) => err,
+        Object::String(message) => Box::new(RuntimeError::new(ErrorKind::Custom("Error".to_string()), message, Some(span))),
+        other => Box::new(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot throw {}, only a string or an error", type_name(&other)),
+            Some(span),
+        )),
+    };
+    EvalResult::Error(err)
+}
+
+fn eval_export(statement: &Statement, env: &mut Environment) -> EvalResult {
+    let name = match statement {
+        Statement::LetStatement { name, .. }
+        | Statement::ConstStatement { name, .. }
+        | Statement::StructDeclaration { name, .. }
+        | Statement::EnumDeclaration { name, .. }
+        | Statement::Trait { name, .. } => name.clone(),
+        // The parser only accepts the declarations above.
+        _ => unreachable!("export of {:?}", statement),
+    };
+    value!(eval_statement(statement, env));
+    let mut exports = env.module.exports.borrow_mut();
+    if !exports.contains(&name) {
+        exports.push(name);
+    }
+    Value(Object::Null)
+}
+
+fn eval_impl(type_name: &str, trait_name: Option<&str>, methods: &[Method], span: Span, env: &mut Environment) -> EvalResult {
+    let target = env.get(type_name);
+    let (type_methods, type_traits) = match &target {
+        Some(Object::StructType(ty)) => (&ty.methods, &ty.traits),
+        Some(Object::EnumType(ty)) => (&ty.methods, &ty.traits),
+        _ => {
+            return EvalResult::Error(Box::new(RuntimeError::new(
+                ErrorKind::Type,
+                format!("cannot impl {}, it is not a struct or enum", type_name),
+                Some(span),
+            )))
+        }
+    };
+
+    let mut functions = vec![];
+    for Method { name, type_parameters, parameters, parameter_types, body, .. } in methods {
+        let bounds = ok!(trait_bounds(type_parameters, parameter_types, env));
+        let function = Function { parameters: parameters.clone(), body: body.clone(), env: env.clone(), bounds };
+        functions.push((name.clone(), function));
+    }
+    if let Some(trait_name) = trait_name {
+        let Some(Object::Trait(tr)) = env.get(trait_name) else {
+            return EvalResult::Error(Box::new(RuntimeError::new(
+                ErrorKind::Type,
+                format!("cannot impl {}, it is not a trait", trait_name),
+                Some(span),
+            )));
+        };
+        if type_traits.borrow().contains(&tr) {
+            let message = format!("{} already implements {}", type_name, tr.name);
+            return EvalResult::Error(Box::new(RuntimeError::new(ErrorKind::Type, message, Some(span))));
+        }
+        ok!(impl_trait(&tr, type_name, &mut functions).map_err(|err| err.at(span)));
+        type_traits.borrow_mut().push(tr);
+    }
+
+    for (name, method) in functions {
+        type_methods.borrow_mut().insert(name, Rc::new(method));
+    }
+    Value(Object::Null)
+}
+
+fn eval_expression(expr: &Expression, env: &mut Environment) -> EvalResult {
     match expr {
-        Expression::NumberLiteral(n) => Object::Integer(n),
-        Expression::Identifier(name) => {
-            if let Some(v) = env.get(&name) {
-                v
+        Expression::NumberLiteral(n) => Value(Object::Integer(*n)),
+        Expression::FloatLiteral(x) => Value(Object::Float(*x)),
+        Expression::StringLiteral(s) => Value(Object::String(s.clone())),
+        Expression::BooleanLiteral(b) => Value(Object::Boolean(*b)),
+        Expression::NullLiteral => Value(Object::Null),
+        Expression::Interpolated(parts) => eval_interpolated(parts, env),
+        Expression::Identifier { name, span } => Value(ok!(eval_identifier(name, *span, env))),
+        Expression::Prefix { operator, right, span } => eval_prefix(operator, right, *span, env),
+        Expression::Infix { left, operator, right, span } => eval_infix(left, operator, right, *span, env),
+        Expression::Assign { target, operator, value, span } => eval_assign(target, operator, value, *span, env),
+        Expression::ArrayLiteral(elements) => eval_array_literal(elements, env),
+        Expression::HashLiteral(pairs) => eval_hash_literal(pairs, env),
+        Expression::StructLiteral { name, fields, span } => eval_struct_literal(name, fields, *span, env),
+        Expression::Field { object, field, span } => eval_field(object, field, *span, env),
+        Expression::Path { object, member, span } => eval_path(object, member, *span, env),
+        Expression::Index { left, index, span } => eval_index(left, index, *span, env),
+        Expression::Slice { left, start, end, span } => eval_slice(left, start.as_deref(), end.as_deref(), *span, env),
+        Expression::FunctionLiteral { type_parameters, parameters, parameter_types, body, .. } => {
+            eval_function_literal(type_parameters, parameters, parameter_types, body, env)
+        }
+        Expression::Call { function, arguments, span } => eval_call(function, arguments, *span, env),
+        Expression::Try { body, catch, finally } => eval_try(body, catch.as_ref(), finally.as_ref(), env),
+        Expression::If { condition, consequence, alternative } => eval_if(condition, consequence, alternative.as_ref(), env),
+        Expression::Match { subject, arms, span } => eval_match(subject, arms, *span, env),
+        Expression::While { condition, body } => eval_while(condition, body, env),
+        Expression::Loop { body } => eval_loop(body, env),
+        Expression::For { variable, iterable, body, span } => eval_for(variable, iterable, body, *span, env),
+    }
+}
+
+fn eval_interpolated(parts: &[InterpolationPart], env: &mut Environment) -> EvalResult {
+    let mut out = String::new();
+    for part in parts {
+        match part {
+            InterpolationPart::Literal(text) => out.push_str(text),
+            InterpolationPart::Expr(expr) => {
+                let value = value!(eval_expression(expr, env));
+                out.push_str(&ok!(display(&value, None)));
+            }
+        }
+    }
+    Value(Object::String(out))
+}
+
+// Bindings shadow builtins of the same name.
+fn eval_identifier(name: &str, span: Span, env: &Environment) -> Result<Object, RuntimeError> {
+    if let Some(v) = env.get(name) {
+        Ok(v)
+    } else if let Some(builtin) = builtins::lookup(name) {
+        Ok(Object::Builtin(builtin))
+    } else {
+        Err(RuntimeError::new(ErrorKind::Name, format!("undefined variable '{}'", name), Some(span)))
+    }
+}
+
+fn eval_prefix(operator: &str, right: &Expression, span: Span, env: &mut Environment) -> EvalResult {
+    let right_val = value!(eval_expression(right, env));
+    Value(ok!(eval_prefix_expression(operator, right_val).map_err(|err| err.at(span))))
+}
+
+fn eval_infix(left: &Expression, operator: &str, right: &Expression, span: Span, env: &mut Environment) -> EvalResult {
+    let left_val = value!(eval_expression(left, env));
+
+    // && and || only evaluate the right side when they need it.
+    match operator {
+        "&&" if !is_truthy(&left_val) => return Value(Object::Boolean(false)),
+        "||" if is_truthy(&left_val) => return Value(Object::Boolean(true)),
+        "&&" | "||" => {
+            let right_val = value!(eval_expression(right, env));
+            return Value(Object::Boolean(is_truthy(&right_val)));
+        }
+        _ => {}
+    }
+
+    let right_val = value!(eval_expression(right, env));
+    Value(ok!(eval_operator(operator, left_val, right_val, span)))
+}
+
+fn eval_array_literal(elements: &[Expression], env: &mut Environment) -> EvalResult {
+    let mut values = Vec::with_capacity(elements.len());
+    for element in elements {
+        values.push(value!(eval_expression(element, env)));
+    }
+    Value(Object::Array(Rc::new(RefCell::new(values))))
+}
+
+fn eval_hash_literal(pairs: &[(Expression, Expression, Span)], env: &mut Environment) -> EvalResult {
+    let mut hash = OrderedHash::new();
+    for (key, value, span) in pairs {
+        let key = value!(eval_expression(key, env));
+        let key = ok!(hash_key(&key, Some(*span)));
+        let value = value!(eval_expression(value, env));
+        hash.insert(key, value);
+    }
+    Value(Object::Hash(Rc::new(RefCell::new(hash))))
+}
+
+fn eval_struct_literal(name: &str, fields: &[(String, Expression)], span: Span, env: &mut Environment) -> EvalResult {
+    let Some(Object::StructType(ty)) = env.get(name) else {
+        return EvalResult::Error(Box::new(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot construct {}, it is not a struct", name),
+            Some(span),
+        )));
+    };
+
+    let mut values = vec![None; ty.fields.len()];
+    for (field, value) in fields {
+        let Some(pos) = ty.field_position(field) else {
+            return EvalResult::Error(Box::new(no_field(&ty, field, span)));
+        };
+        if values[pos].is_some() {
+            return EvalResult::Error(Box::new(RuntimeError::new(
+                ErrorKind::Field,
+                format!("field '{}' is given twice", field),
+                Some(span),
+            )));
+        }
+        values[pos] = Some(value!(eval_expression(value, env)));
+    }
+
+    let mut fields = Vec::with_capacity(values.len());
+    for (name, value) in ty.fields.iter().zip(values) {
+        match value {
+            Some(value) => fields.push(value),
+            None => {
+                return EvalResult::Error(Box::new(RuntimeError::new(
+                    ErrorKind::Field,
+                    format!("missing field '{}' in {}", name, ty.name),
+                    Some(span),
+                )))
+            }
+        }
+    }
+    Value(Object::Instance(Rc::new(RefCell::new(Instance { ty, fields }))))
+}
+
+fn eval_field(object: &Expression, field: &str, span: Span, env: &mut Environment) -> EvalResult {
+    let object = value!(eval_expression(object, env));
+    Value(ok!(field_value(&object, field, span)))
+}
+
+fn eval_path(object: &Expression, member: &str, span: Span, env: &mut Environment) -> EvalResult {
+    let object = value!(eval_expression(object, env));
+    Value(ok!(path_value(&object, member, span)))
+}
+
+fn eval_index(left: &Expression, index: &Expression, span: Span, env: &mut Environment) -> EvalResult {
+    let left = value!(eval_expression(left, env));
+    let index = value!(eval_expression(index, env));
+    Value(ok!(index_value(&left, &index, span)))
+}
+
+fn eval_slice(
+    left: &Expression,
+    start: Option<&Expression>,
+    end: Option<&Expression>,
+    span: Span,
+    env: &mut Environment,
+) -> EvalResult {
+    let left = value!(eval_expression(left, env));
+    let start = match start {
+        Some(start) => Some(value!(eval_expression(start, env))),
+        None => None,
+    };
+    let end = match end {
+        Some(end) => Some(value!(eval_expression(end, env))),
+        None => None,
+    };
+    Value(ok!(slice_value(&left, start, end, span)))
+}
+
+fn eval_function_literal(
+    type_parameters: &[TypeParameter],
+    parameters: &[String],
+    parameter_types: &[Option<TypeAnnotation>],
+    body: &Block,
+    env: &Environment,
+) -> EvalResult {
+    let bounds = ok!(trait_bounds(type_parameters, parameter_types, env));
+    let function = Function { parameters: parameters.to_vec(), body: body.clone(), env: env.clone(), bounds };
+    Value(Object::Function(Rc::new(function)))
+}
+
+fn eval_call(function: &Expression, arguments: &[Expression], span: Span, env: &mut Environment) -> EvalResult {
+    let name = callee_name(function);
+
+    // `p.norm()` calls the method with `p` as its first argument.
+    let mut args = Vec::with_capacity(arguments.len() + 1);
+    let function = match function {
+        Expression::Field { object, field, span } => {
+            let object = value!(eval_expression(object, env));
+            match method_of(&object, field) {
+                Some(method) => {
+                    args.push(object);
+                    Object::Function(method)
+                }
+                None => ok!(field_value(&object, field, *span)),
+            }
+        }
+        function => value!(eval_expression(function, env)),
+    };
+
+    for arg in arguments {
+        args.push(value!(eval_expression(arg, env)));
+    }
+
+    apply_function(function, args, name, span)
+}
+
+// The body's value, or the catch block's if the body threw. A finally
+// block always runs; its value is dropped, but a return, break or
+// throw inside it wins over what the rest produced.
+fn eval_try(body: &Block, catch: Option<&Catch>, finally: Option<&Block>, env: &mut Environment) -> EvalResult {
+    let mut result = eval_block(body, &mut env.enclosed());
+    if let (EvalResult::Error(err), Some(catch)) = (&result, catch) {
+        let mut scope = env.enclosed();
+        if let Some(name) = &catch.name {
+            scope.define(name.clone(), Object::Error(err.clone()));
+        }
+        result = eval_block(&catch.body, &mut scope);
+    }
+    if let Some(finally) = finally {
+        value!(eval_block(finally, &mut env.enclosed()));
+    }
+    result
+}
+
+// The branch that runs gives the `if` its value; no branch gives null.
+fn eval_if(condition: &Expression, consequence: &Block, alternative: Option<&Block>, env: &mut Environment) -> EvalResult {
+    let condition = value!(eval_expression(condition, env));
+    if is_truthy(&condition) {
+        return eval_block(consequence, &mut env.enclosed());
+    }
+    match alternative {
+        Some(alternative) => eval_block(alternative, &mut env.enclosed()),
+        None => Value(Object::Null),
+    }
+}
+
+// The first arm whose pattern matches, and whose guard passes, gives the
+// match its value. Its bindings live in a scope of their own.
+fn eval_match(subject: &Expression, arms: &[MatchArm], span: Span, env: &mut Environment) -> EvalResult {
+    let subject = value!(eval_expression(subject, env));
+
+    for arm in arms {
+        let mut bindings = vec![];
+        if !ok!(match_pattern(&arm.pattern, &subject, env, &mut bindings)) {
+            continue;
+        }
+
+        let mut scope = env.enclosed();
+        for (name, val) in bindings {
+            scope.define(name, val);
+        }
+        if let Some(guard) = &arm.guard {
+            if !is_truthy(&value!(eval_expression(guard, &mut scope))) {
+                continue;
+            }
+        }
+        return eval_block(&arm.body, &mut scope);
+    }
+
+    EvalResult::Error(Box::new(RuntimeError::new(
+        ErrorKind::Match,
+        format!("no match arm matches {}", subject),
+        Some(span),
+    )))
+}
+
+// A loop's value is what `break` gives it, or null once it runs out.
+fn eval_while(condition: &Expression, body: &Block, env: &mut Environment) -> EvalResult {
+    loop {
+        let condition = value!(eval_expression(condition, env));
+        if !is_truthy(&condition) {
+            return Value(Object::Null);
+        }
+        if let Some(done) = eval_iteration(body, env.enclosed()) {
+            return done;
+        }
+    }
+}
+
+fn eval_loop(body: &Block, env: &mut Environment) -> EvalResult {
+    loop {
+        if let Some(done) = eval_iteration(body, env.enclosed()) {
+            return done;
+        }
+    }
+}
+
+fn eval_for(variable: &str, iterable: &Expression, body: &Block, span: Span, env: &mut Environment) -> EvalResult {
+    let iterable = value!(eval_expression(iterable, env));
+    let items = ok!(iterate(&iterable, span));
+
+    for item in items {
+        let scope = env.enclosed();
+        scope.define(variable.to_string(), item);
+        if let Some(done) = eval_iteration(body, scope) {
+            return done;
+        }
+    }
+    Value(Object::Null)
+}
+
+// `x = v`, `x += v`, `a[i] = v` and so on. A compound operator reads the
+// target before the right side is evaluated.
+fn eval_assign(target: &Expression, operator: &str, value: &Expression, span: Span, env: &mut Environment) -> EvalResult {
+    let combine = |current: Object, value: Object| eval_operator(operator.trim_end_matches('='), current, value, span);
+
+    match target {
+        Expression::Identifier { name, .. } => {
+            let value = if operator == "=" {
+                value!(eval_expression(value, env))
+            } else {
+                let Some(current) = env.get(name) else {
+                    return EvalResult::Error(Box::new(assign_error(AssignError::Undeclared, name, span)));
+                };
+                ok!(combine(current, value!(eval_expression(value, env))))
+            };
+
+            if let Err(err) = env.assign(name, value.clone()) {
+                return EvalResult::Error(Box::new(assign_error(err, name, span)));
+            }
+            Value(value)
+        }
+        Expression::Index { left, index, span } => {
+            let container = value!(eval_expression(left, env));
+            let index = value!(eval_expression(index, env));
+
+            let value = if operator == "=" {
+                value!(eval_expression(value, env))
+            } else {
+                let current = ok!(index_value(&container, &index, *span));
+                ok!(combine(current, value!(eval_expression(value, env))))
+            };
+
+            ok!(set_index(&container, &index, value.clone(), *span));
+            Value(value)
+        }
+        Expression::Field { object, field, span } => {
+            let object = value!(eval_expression(object, env));
+
+            let value = if operator == "=" {
+                value!(eval_expression(value, env))
             } else {
-                Object::Null
+                let current = ok!(field_value(&object, field, *span));
+                ok!(combine(current, value!(eval_expression(value, env))))
+            };
+
+            ok!(set_field(&object, field, value.clone(), *span));
+            Value(value)
+        }
+        // The parser only builds assignments to names, indexes and fields.
+        _ => Value(Object::Null),
+    }
+}
+
+// Whether `value` fits `pattern`. The names it binds are pushed onto
+// `bindings`, and are only meaningful when it does fit. `env` is where
+// variant names in the pattern are looked up.
+fn match_pattern(
+    pattern: &Pattern,
+    value: &Object,
+    env: &Environment,
+    bindings: &mut Vec<(String, Object)>,
+) -> Result<bool, RuntimeError> {
+    match pattern {
+        Pattern::Wildcard => Ok(true),
+        // A bare name for a unit variant, such as `None`, matches that variant
+        // rather than binding a new name.
+        Pattern::Binding(name) => match unit_variant(name, env) {
+            Some(variant) => Ok(*value == variant),
+            None => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
             }
+        },
+        // Compared with `==`, so 1 matches 1.0.
+        Pattern::Literal(literal) => {
+            Ok(eval_infix_expression("==", literal_value(literal), value.clone())? == Object::Boolean(true))
         }
-        Expression::Prefix { operator, right } => {
-            let right_val = eval_expression(*right, env);
-            eval_prefix_expression(&operator, right_val)
+        Pattern::Array(patterns) => {
+            let Object::Array(elements) = value else {
+                return Ok(false);
+            };
+            let elements = elements.borrow();
+
+            let Some(rest_at) = patterns.iter().position(|p| matches!(p, Pattern::Rest(_))) else {
+                return Ok(patterns.len() == elements.len() && match_all(patterns, &elements, env, bindings)?);
+            };
+
+            // Patterns before the `..` take elements from the front, those
+            // after it from the back, and the rest collects what is left.
+            let after = patterns.len() - rest_at - 1;
+            if elements.len() < rest_at + after {
+                return Ok(false);
+            }
+            let rest_end = elements.len() - after;
+
+            if let Pattern::Rest(Some(name)) = &patterns[rest_at] {
+                let rest = elements[rest_at..rest_end].to_vec();
+                bindings.push((name.clone(), Object::Array(Rc::new(RefCell::new(rest)))));
+            }
+            Ok(match_all(&patterns[..rest_at], &elements[..rest_at], env, bindings)?
+                && match_all(&patterns[rest_at + 1..], &elements[rest_end..], env, bindings)?)
         }
-        Expression::Infix { left, operator, right } => {
-            let left_val = eval_expression(*left, env);
-            let right_val = eval_expression(*right, env);
-            eval_infix_expression(&operator, left_val, right_val)
+        // Only valid inside an array pattern, where it is handled above.
+        Pattern::Rest(_) => Ok(false),
+        Pattern::Hash(pairs) => {
+            let Object::Hash(hash) = value else {
+                return Ok(false);
+            };
+            let hash = hash.borrow();
+
+            for (key, pattern) in pairs {
+                let found = HashKey::from_object(&literal_value(key)).and_then(|key| hash.get(&key));
+                match found {
+                    Some(found) if match_pattern(pattern, found, env, bindings)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            Ok(true)
+        }
+        Pattern::Variant { enum_name, variant, fields, span } => {
+            let (ty, position) = resolve_variant(enum_name.as_deref(), variant, env, *span)?;
+
+            let expected = ty.variants[position].fields.len();
+            if fields.len() != expected {
+                return Err(RuntimeError::new(
+                    ErrorKind::Match,
+                    format!("{}::{} has {} fields, but the pattern has {}", ty.name, variant, expected, fields.len()),
+                    Some(*span),
+                ));
+            }
+
+            match value {
+                Object::Variant(value) if value.ty == ty && value.variant == position => {
+                    match_all(fields, &value.values, env, bindings)
+                }
+                _ => Ok(false),
+            }
         }
     }
 }
 
-fn eval_prefix_expression(operator: &str, right: Object) -> Object {
-    match operator {
-        "!" => match right {
-            Object::Boolean(b) => Object::Boolean(!b),
-            Object::Null => Object::Boolean(true),
-            Object::Integer(i) => Object::Boolean(i == 0),
+// Matches each pattern against the value in the same position.
+fn match_all(
+    patterns: &[Pattern],
+    values: &[Object],
+    env: &Environment,
+    bindings: &mut Vec<(String, Object)>,
+) -> Result<bool, RuntimeError> {
+    for (pattern, value) in patterns.iter().zip(values) {
+        if !match_pattern(pattern, value, env, bindings)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// The enum and variant index a variant pattern names: `Shape::Circle`
+// through the enum, or a bare `Some` through its constructor or value.
+fn resolve_variant(
+    enum_name: Option<&str>,
+    variant: &str,
+    env: &Environment,
+    span: Span,
+) -> Result<(Rc<EnumType>, usize), RuntimeError> {
+    let found = match enum_name {
+        Some(enum_name) => match env.get(enum_name) {
+            Some(Object::EnumType(ty)) => ty.variant_position(variant).map(|position| (ty, position)),
+            _ => None,
         },
-        "-" => match right {
-            Object::Integer(i) => Object::Integer(-i),
-            _ => Object::Null,
+        None => match env.get(variant) {
+            Some(Object::Constructor(ty, position)) => Some((ty, position)),
+            Some(Object::Variant(value)) if value.ty.variants[value.variant].name == variant => {
+                Some((value.ty.clone(), value.variant))
+            }
+            _ => None,
         },
+    };
+
+    found.ok_or_else(|| {
+        let path = match enum_name {
+            Some(enum_name) => format!("{}::{}", enum_name, variant),
+            None => variant.to_string(),
+        };
+        RuntimeError::new(ErrorKind::Name, format!("{} is not an enum variant", path), Some(span))
+    })
+}
+
+// The value `name` refers to, if it is a unit variant of that same name.
+fn unit_variant(name: &str, env: &Environment) -> Option<Object> {
+    match env.get(name)? {
+        Object::Variant(value) if value.ty.variants[value.variant].name == name => Some(Object::Variant(value)),
+        _ => None,
+    }
+}
+
+// The value of a literal in a pattern, such as `"text"` or `-1`.
+fn literal_value(literal: &Expression) -> Object {
+    match literal {
+        Expression::NumberLiteral(n) => Object::Integer(*n),
+        Expression::FloatLiteral(x) => Object::Float(*x),
+        Expression::StringLiteral(s) => Object::String(s.clone()),
+        Expression::BooleanLiteral(b) => Object::Boolean(*b),
+        // The parser only allows `-` before a number here, which cannot fail.
+        Expression::Prefix { operator, right, .. } => {
+            eval_prefix_expression(operator, literal_value(right)).unwrap_or(Object::Null)
+        }
         _ => Object::Null,
     }
 }
 
-fn eval_infix_expression(operator: &str, left: Object, right: Object) -> Object {
+// Runs one pass of a loop body in `scope`. Gives back what the loop should
+// finish with, or None to go round again.
+fn eval_iteration(body: &Block, mut scope: Environment) -> Option<EvalResult> {
+    match eval_block(body, &mut scope) {
+        Value(_) | EvalResult::Continue => None,
+        EvalResult::Break(obj) => Some(Value(obj)),
+        signal => Some(signal),
+    }
+}
+
+// The values a `for` loop walks over: the elements of an array, the keys
+// of a hash in insertion order, or the characters of a string.
+fn iterate(obj: &Object, span: Span) -> Result<Vec<Object>, RuntimeError> {
+    match obj {
+        Object::Array(elements) => Ok(elements.borrow().clone()),
+        Object::Hash(hash) => Ok(hash.borrow().iter().map(|(key, _)| key.to_object()).collect()),
+        Object::String(s) => Ok(s.chars().map(|c| Object::String(c.to_string())).collect()),
+        other => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot iterate over {}", type_name(other)),
+            Some(span),
+        )),
+    }
+}
+
+// p.x on an instance gives the field; Point.name on a struct type gives the
+// method, so methods without `self` can be called as Point.origin().
+fn field_value(object: &Object, field: &str, span: Span) -> Result<Object, RuntimeError> {
+    match object {
+        Object::Instance(instance) => {
+            let instance = instance.borrow();
+            match instance.ty.field_position(field) {
+                Some(pos) => Ok(instance.fields[pos].clone()),
+                None => Err(no_field(&instance.ty, field, span)),
+            }
+        }
+        Object::StructType(ty) => ty.method(field).map(Object::Function).ok_or_else(|| {
+            RuntimeError::new(ErrorKind::Field, format!("{} has no method '{}'", ty.name, field), Some(span))
+        }),
+        Object::Error(err) if field == "message" => Ok(Object::String(err.message.clone())),
+        Object::Error(err) if field == "kind" => Ok(Object::String(err.kind.to_string())),
+        Object::Error(err) if field == "stack" => {
+            let frames = err.stack.iter().map(|frame| Object::String(frame.to_string())).collect();
+            Ok(Object::Array(Rc::new(RefCell::new(frames))))
+        }
+        Object::Module(module) => module.get(field).cloned().ok_or_else(|| {
+            RuntimeError::new(ErrorKind::Field, format!("module \"{}\" has no export '{}'", module.name, field), Some(span))
+        }),
+        other => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot access field '{}' on {}", field, type_name(other)),
+            Some(span),
+        )),
+    }
+}
+
+fn set_field(object: &Object, field: &str, value: Object, span: Span) -> Result<(), RuntimeError> {
+    match object {
+        Object::Instance(instance) => {
+            let mut instance = instance.borrow_mut();
+            match instance.ty.field_position(field) {
+                Some(pos) => {
+                    instance.fields[pos] = value;
+                    Ok(())
+                }
+                None => Err(no_field(&instance.ty, field, span)),
+            }
+        }
+        other => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot assign to field '{}' on {}", field, type_name(other)),
+            Some(span),
+        )),
+    }
+}
+
+// Shape::Circle gives a variant (or, with a payload, its constructor);
+// Point::origin gives a method, as Point.origin does.
+fn path_value(object: &Object, member: &str, span: Span) -> Result<Object, RuntimeError> {
+    match object {
+        Object::EnumType(ty) => {
+            if let Some(position) = ty.variant_position(member) {
+                return Ok(variant_value(ty, position));
+            }
+            ty.methods.borrow().get(member).cloned().map(Object::Function).ok_or_else(|| {
+                RuntimeError::new(ErrorKind::Name, format!("{} has no variant '{}'", ty.name, member), Some(span))
+            })
+        }
+        Object::StructType(_) | Object::Module(_) => field_value(object, member, span),
+        other => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot use '::' on {}", type_name(other)),
+            Some(span),
+        )),
+    }
+}
+
+// A unit variant is a value already; one with a payload needs building.
+fn variant_value(ty: &Rc<EnumType>, position: usize) -> Object {
+    if ty.variants[position].fields.is_empty() {
+        Object::Variant(Rc::new(VariantValue { ty: ty.clone(), variant: position, values: vec![] }))
+    } else {
+        Object::Constructor(ty.clone(), position)
+    }
+}
+
+// The method `p.name(...)` calls, if `name` is a method of p's type rather
+// than one of its fields.
+fn method_of(object: &Object, name: &str) -> Option<Rc<Function>> {
+    match object {
+        Object::Instance(instance) => {
+            let ty = &instance.borrow().ty;
+            if ty.field_position(name).is_some() {
+                return None;
+            }
+            ty.method(name)
+        }
+        Object::Variant(value) => value.ty.methods.borrow().get(name).cloned(),
+        _ => None,
+    }
+}
+
+fn no_field(ty: &StructType, field: &str, span: Span) -> RuntimeError {
+    RuntimeError::new(ErrorKind::Field, format!("{} has no field '{}'", ty.name, field), Some(span))
+}
+
+// a[i]: arrays give an element, strings a one-character string, hashes the
+// value stored under that key.
+fn index_value(container: &Object, index: &Object, span: Span) -> Result<Object, RuntimeError> {
+    match container {
+        Object::Hash(hash) => {
+            let key = hash_key(index, Some(span))?;
+            hash.borrow().get(&key).cloned().ok_or_else(|| {
+                RuntimeError::new(ErrorKind::Key, format!("key {} not found in hash", key), Some(span))
+            })
+        }
+        Object::Array(elements) => {
+            let elements = elements.borrow();
+            let pos = element_position(index, elements.len(), span)?;
+            Ok(elements[pos].clone())
+        }
+        Object::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let pos = element_position(index, chars.len(), span)?;
+            Ok(Object::String(chars[pos].to_string()))
+        }
+        other => Err(RuntimeError::new(ErrorKind::Type, format!("cannot index {}", type_name(other)), Some(span))),
+    }
+}
+
+// a[i] = value. Strings are immutable, so only arrays and hashes can be
+// written to. Assigning to a new hash key adds it.
+fn set_index(container: &Object, index: &Object, value: Object, span: Span) -> Result<(), RuntimeError> {
+    match container {
+        Object::Hash(hash) => {
+            let key = hash_key(index, Some(span))?;
+            hash.borrow_mut().insert(key, value);
+            Ok(())
+        }
+        Object::Array(elements) => {
+            let mut elements = elements.borrow_mut();
+            let pos = element_position(index, elements.len(), span)?;
+            elements[pos] = value;
+            Ok(())
+        }
+        other => Err(RuntimeError::new(ErrorKind::Type, format!("cannot assign into {}", type_name(other)), Some(span))),
+    }
+}
+
+// a[start:end] copies out the elements or characters in that range.
+fn slice_value(container: &Object, start: Option<Object>, end: Option<Object>, span: Span) -> Result<Object, RuntimeError> {
+    match container {
+        Object::Array(elements) => {
+            let elements = elements.borrow();
+            let (start, end) = slice_bounds(start, end, elements.len(), span)?;
+            Ok(Object::Array(Rc::new(RefCell::new(elements[start..end].to_vec()))))
+        }
+        Object::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (start, end) = slice_bounds(start, end, chars.len(), span)?;
+            Ok(Object::String(chars[start..end].iter().collect()))
+        }
+        other => Err(RuntimeError::new(ErrorKind::Type, format!("cannot slice {}", type_name(other)), Some(span))),
+    }
+}
+
+// Turns an index into a position in 0..len, where -1 is the last element.
+fn element_position(index: &Object, len: usize, span: Span) -> Result<usize, RuntimeError> {
+    let i = integer_index(index, span)?;
+    match from_end(i, len) {
+        Some(pos) if pos < len => Ok(pos),
+        _ => Err(RuntimeError::new(
+            ErrorKind::Index,
+            format!("index {} out of bounds for length {}", i, len),
+            Some(span),
+        )),
+    }
+}
+
+// Slice bounds default to the whole range and may count from the end, but
+// must stay within 0..=len and must not cross.
+fn slice_bounds(start: Option<Object>, end: Option<Object>, len: usize, span: Span) -> Result<(usize, usize), RuntimeError> {
+    let start = start.map(|s| integer_index(&s, span)).transpose()?;
+    let end = end.map(|e| integer_index(&e, span)).transpose()?;
+
+    let from = start.map_or(Some(0), |i| from_end(i, len));
+    let to = end.map_or(Some(len), |i| from_end(i, len));
+    match (from, to) {
+        (Some(from), Some(to)) if from <= to && to <= len => Ok((from, to)),
+        _ => {
+            let bound = |b: Option<i64>| b.map_or(String::new(), |b| b.to_string());
+            Err(RuntimeError::new(
+                ErrorKind::Index,
+                format!("slice {}:{} out of bounds for length {}", bound(start), bound(end), len),
+                Some(span),
+            ))
+        }
+    }
+}
+
+fn hash_key(obj: &Object, span: Option<Span>) -> Result<HashKey, RuntimeError> {
+    if let Some(hash) = builtin_method(obj, "Hash", "hash") {
+        let key = hash_key(&call_method(hash, "hash", vec![obj.clone()], span)?, span)?;
+        return Ok(HashKey::Custom(Rc::new(CustomKey { key, value: obj.clone() })));
+    }
+    HashKey::from_object(obj).ok_or_else(|| {
+        RuntimeError::new(ErrorKind::Type, format!("cannot use {} as a hash key", type_name(obj)), span)
+    })
+}
+
+fn integer_index(index: &Object, span: Span) -> Result<i64, RuntimeError> {
+    match index {
+        Object::Integer(i) => Ok(*i),
+        other => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("index must be an integer, not {}", type_name(other)),
+            Some(span),
+        )),
+    }
+}
+
+// Counts a negative index back from `len`; None if it reaches past the start.
+fn from_end(i: i64, len: usize) -> Option<usize> {
+    if i < 0 {
+        len.checked_sub(i.unsigned_abs() as usize)
+    } else {
+        Some(i as usize)
+    }
+}
+
+// The name a call's stack frame shows: `f` for `f(x)`, `norm` for `p.norm()`.
+fn callee_name(function: &Expression) -> String {
+    match function {
+        Expression::Identifier { name, .. } => name.clone(),
+        Expression::Field { field, .. } => field.clone(),
+        Expression::Path { member, .. } => member.clone(),
+        _ => "<anonymous>".to_string(),
+    }
+}
+
+// How many function calls deep evaluation may go. Each call takes several
+// native stack frames, so the host thread needs a stack large enough for this
+// many; main runs the interpreter on one.
+pub const MAX_CALL_DEPTH: usize = 2000;
+
+thread_local! {
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+fn apply_function(function: Object, args: Vec<Object>, name: String, span: Span) -> EvalResult {
+    let func = match function {
+        Object::Function(func) => func,
+        function => return apply_native(function, args, span),
+    };
+    ok!(check_arguments(&func, &args, span));
+
+    let mut call_env = func.env.enclosed();
+    for (name, arg) in func.parameters.iter().zip(args) {
+        call_env.define(name.clone(), arg);
+    }
+
+    // Runaway recursion is an error scripts can catch, not a stack overflow.
+    let depth = CALL_DEPTH.with(|depth| depth.get());
+    if depth >= MAX_CALL_DEPTH {
+        return EvalResult::Error(Box::new(RuntimeError::new(
+            ErrorKind::Recursion,
+            format!("maximum call depth of {} exceeded", MAX_CALL_DEPTH),
+            Some(span),
+        )));
+    }
+    CALL_DEPTH.with(|d| d.set(depth + 1));
+    let result = eval_block(&func.body, &mut call_env);
+    CALL_DEPTH.with(|d| d.set(depth));
+
+    // A `return` ends this call only. An error leaving the call records it.
+    match result {
+        Return(obj) => Value(obj),
+        EvalResult::Error(mut err) => {
+            err.stack.push(Frame { function: name, span });
+            EvalResult::Error(err)
+        }
+        other => other,
+    }
+}
+
+// Calls what is not a function literal: a builtin or a variant constructor.
+fn apply_native(function: Object, args: Vec<Object>, span: Span) -> EvalResult {
+    match function {
+        Object::Builtin(builtin) => match (builtin.func)(args, span) {
+            Ok(obj) => Value(obj),
+            Err(err) => EvalResult::Error(Box::new(err)),
+        },
+        Object::Constructor(ty, variant) => {
+            let expected = ty.variants[variant].fields.len();
+            if args.len() != expected {
+                return EvalResult::Error(Box::new(RuntimeError::new(
+                    ErrorKind::Type,
+                    format!("expected {} arguments but got {}", expected, args.len()),
+                    Some(span),
+                )));
+            }
+            Value(Object::Variant(Rc::new(VariantValue { ty, variant, values: args })))
+        }
+        function => EvalResult::Error(Box::new(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot call {}, it is not a function", type_name(&function)),
+            Some(span),
+        ))),
+    }
+}
+
+// Checks the number of arguments, and that they implement the traits the
+// function's type parameters are bounded by.
+fn check_arguments(func: &Function, args: &[Object], span: Span) -> Result<(), RuntimeError> {
+    if args.len() != func.parameters.len() {
+        return Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("expected {} arguments but got {}", func.parameters.len(), args.len()),
+            Some(span),
+        ));
+    }
+
+    for (position, tr) in &func.bounds {
+        let arg = &args[*position];
+        if !implements(arg, tr) {
+            return Err(RuntimeError::new(
+                ErrorKind::Type,
+                format!("argument {}: {} does not implement {}", position + 1, value_type_name(arg), tr.name),
+                Some(span),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Calls `method` the way an operator or builtin does on a user type's
+// behalf. Without a location, the call is left out of the error's stack.
+fn call_method(method: Rc<Function>, name: &str, args: Vec<Object>, span: Option<Span>) -> Result<Object, RuntimeError> {
+    match apply_function(Object::Function(method), args, name.to_string(), span.unwrap_or_default()) {
+        Value(obj) => Ok(obj),
+        EvalResult::Error(mut err) => {
+            if span.is_none() {
+                err.stack.pop();
+            }
+            Err(*err)
+        }
+        // A call only ends with a value or an error.
+        _ => Ok(Object::Null),
+    }
+}
+
+// The traits each argument must implement: those bounding the type
+// parameter its parameter is annotated with. They are looked up where
+// the function is defined.
+fn trait_bounds(
+    type_parameters: &[TypeParameter],
+    parameter_types: &[Option<TypeAnnotation>],
+    env: &Environment,
+) -> Result<Vec<(usize, Rc<Trait>)>, RuntimeError> {
+    let mut bounds = vec![];
+    for parameter in type_parameters {
+        let mut traits = vec![];
+        for bound in &parameter.bounds {
+            match env.get(bound) {
+                Some(Object::Trait(tr)) => traits.push(tr),
+                _ => {
+                    let message = format!("'{}' in the bounds of {} is not a trait", bound, parameter.name);
+                    return Err(RuntimeError::new(ErrorKind::Type, message, Some(parameter.span)));
+                }
+            }
+        }
+        for (position, annotation) in parameter_types.iter().enumerate() {
+            if let Some(TypeAnnotation { ty: TypeExpr::Named(name), .. }) = annotation {
+                if *name == parameter.name {
+                    bounds.extend(traits.iter().map(|tr| (position, tr.clone())));
+                }
+            }
+        }
+    }
+    Ok(bounds)
+}
+
+// Checks an impl's methods against the trait and adds the defaults it
+// left out. Errors come back without a location.
+fn impl_trait(tr: &Trait, type_name: &str, functions: &mut Vec<(String, Function)>) -> Result<(), RuntimeError> {
+    for (name, function) in functions.iter() {
+        let Some(declared) = tr.methods.iter().find(|method| method.name == *name) else {
+            let message = format!("method '{}' is not a member of trait {}", name, tr.name);
+            return Err(RuntimeError::new(ErrorKind::Type, message, None));
+        };
+        if declared.parameters.len() != function.parameters.len() {
+            let message = format!(
+                "method '{}' takes {} parameters in trait {} but {} here",
+                name,
+                declared.parameters.len(),
+                tr.name,
+                function.parameters.len()
+            );
+            return Err(RuntimeError::new(ErrorKind::Type, message, None));
+        }
+    }
+
+    for method in &tr.methods {
+        if functions.iter().any(|(name, _)| *name == method.name) {
+            continue;
+        }
+        let Some(body) = &method.default else {
+            let message = format!("impl {} for {} is missing method '{}'", tr.name, type_name, method.name);
+            return Err(RuntimeError::new(ErrorKind::Type, message, None));
+        };
+        let bounds = trait_bounds(&method.type_parameters, &method.parameter_types, &tr.env)?;
+        let function = Function { parameters: method.parameters.clone(), body: body.clone(), env: tr.env.clone(), bounds };
+        functions.push((method.name.clone(), function));
+    }
+    Ok(())
+}
+
+// Whether `obj`'s type implements `tr`. Values of built-in types implement
+// the prelude's traits for what they support natively.
+fn implements(obj: &Object, tr: &Rc<Trait>) -> bool {
+    match obj {
+        Object::Instance(instance) => instance.borrow().ty.traits.borrow().contains(tr),
+        Object::Variant(value) => value.ty.traits.borrow().contains(tr),
+        _ if !tr.builtin => false,
+        Object::Integer(_) | Object::String(_) => true,
+        Object::Float(_) => tr.name != "Hash",
+        Object::Boolean(_) => tr.name != "Ord",
+        Object::Array(_) | Object::Hash(_) | Object::Null => matches!(tr.name.as_str(), "Eq" | "Display"),
+        _ => false,
+    }
+}
+
+// The method behind a prelude trait, such as `eq` for Eq, if `obj`'s type
+// implements that trait.
+fn builtin_method(obj: &Object, trait_name: &str, method: &str) -> Option<Rc<Function>> {
+    let implemented = |traits: &Traits| traits.borrow().iter().any(|tr| tr.builtin && tr.name == trait_name);
+    match obj {
+        Object::Instance(instance) => {
+            let ty = instance.borrow().ty.clone();
+            implemented(&ty.traits).then(|| ty.method(method)).flatten()
+        }
+        Object::Variant(value) => {
+            implemented(&value.ty.traits).then(|| value.ty.methods.borrow().get(method).cloned()).flatten()
+        }
+        _ => None,
+    }
+}
+
+// How `print`, string interpolation and the REPL show a value: through its
+// type's `to_string` if it implements Display.
+pub fn display(obj: &Object, span: Option<Span>) -> Result<String, RuntimeError> {
+    let Some(to_string) = builtin_method(obj, "Display", "to_string") else {
+        return Ok(obj.to_string());
+    };
+    match call_method(to_string, "to_string", vec![obj.clone()], span)? {
+        Object::String(s) => Ok(s),
+        other => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("to_string must return a string, not {}", type_name(&other)),
+            span,
+        )),
+    }
+}
+
+// A type name for messages: a struct or enum's own name, or the kind of value.
+fn value_type_name(obj: &Object) -> String {
+    match obj {
+        Object::Instance(instance) => instance.borrow().ty.name.clone(),
+        Object::Variant(value) => value.ty.name.clone(),
+        other => type_name(other).to_string(),
+    }
+}
+
+fn assign_error(err: AssignError, name: &str, span: Span) -> RuntimeError {
+    let message = match err {
+        AssignError::Undeclared => format!("cannot assign to undeclared variable '{}'", name),
+        AssignError::Const => format!("cannot assign to const '{}'", name),
+    };
+    RuntimeError::new(ErrorKind::Name, message, Some(span))
+}
+
+fn type_name(obj: &Object) -> &'static str {
+    match obj {
+        Object::Integer(_) => "integer",
+        Object::Float(_) => "float",
+        Object::Boolean(_) => "boolean",
+        Object::String(_) => "string",
+        Object::Array(_) => "array",
+        Object::Hash(_) => "hash",
+        Object::StructType(_) | Object::EnumType(_) => "type",
+        Object::Instance(_) => "struct",
+        Object::Variant(_) => "enum",
+        Object::Constructor(..) => "function",
+        Object::Trait(_) => "trait",
+        Object::Function(_) | Object::Builtin(_) => "function",
+        Object::Module(_) => "module",
+        Object::Error(_) => "error",
+        Object::Null => "null",
+    }
+}
+
+// null, false, 0, 0.0, "", [] and {} are falsy; everything else is truthy.
+fn is_truthy(obj: &Object) -> bool {
+    match obj {
+        Object::Boolean(b) => *b,
+        Object::Null => false,
+        Object::Integer(i) => *i != 0,
+        Object::Float(x) => *x != 0.0,
+        Object::String(s) => !s.is_empty(),
+        Object::Array(elements) => !elements.borrow().is_empty(),
+        Object::Hash(hash) => !hash.borrow().is_empty(),
+        Object::StructType(_) | Object::Instance(_) => true,
+        Object::EnumType(_) | Object::Variant(_) | Object::Constructor(..) | Object::Trait(_) => true,
+        Object::Function(_) | Object::Builtin(_) | Object::Error(_) => true,
+        Object::Module(_) => true,
+    }
+}
+
+fn eval_prefix_expression(operator: &str, right: Object) -> Result<Object, RuntimeError> {
+    match (operator, right) {
+        ("!", right) => Ok(Object::Boolean(!is_truthy(&right))),
+        ("-", Object::Integer(i)) => i.checked_neg().map(Object::Integer).ok_or_else(overflow),
+        ("-", Object::Float(x)) => Ok(Object::Float(-x)),
+        (_, right) => Err(RuntimeError::new(
+            ErrorKind::Type,
+            format!("cannot apply '{}' to {}", operator, type_name(&right)),
+            None,
+        )),
+    }
+}
+
+// `left <operator> right` at `span`. Equality and ordering on a value whose
+// type implements Eq or Ord go through its `eq` or `cmp` method.
+fn eval_operator(operator: &str, left: Object, right: Object, span: Span) -> Result<Object, RuntimeError> {
+    match operator {
+        // Mixed ints and floats are compared as numbers below.
+        "==" | "!=" if !matches!((&left, &right), (Object::Integer(_), Object::Float(_)) | (Object::Float(_), Object::Integer(_))) => {
+            let equal = equal(&left, &right, Some(span), &mut vec![])?;
+            return Ok(Object::Boolean(equal == (operator == "==")));
+        }
+        "<" | ">" | "<=" | ">=" => {
+            if let Some(cmp) = builtin_method(&left, "Ord", "cmp") {
+                let ordering = match call_method(cmp, "cmp", vec![left, right], Some(span))? {
+                    Object::Integer(ordering) => ordering,
+                    other => {
+                        let message = format!("cmp must return an integer, not {}", type_name(&other));
+                        return Err(RuntimeError::new(ErrorKind::Type, message, Some(span)));
+                    }
+                };
+                let result = match operator {
+                    "<" => ordering < 0,
+                    ">" => ordering > 0,
+                    "<=" => ordering <= 0,
+                    _ => ordering >= 0,
+                };
+                return Ok(Object::Boolean(result));
+            }
+        }
+        _ => {}
+    }
+    eval_infix_expression(operator, left, right).map_err(|err| err.at(span))
+}
+
+// Operator errors come back without a location; callers add the operator's.
+fn eval_infix_expression(operator: &str, left: Object, right: Object) -> Result<Object, RuntimeError> {
     use Object::*;
     match (left, right) {
         (Integer(l), Integer(r)) => match operator {
-            "+" => Integer(l + r),
-            "-" => Integer(l - r),
-            "*" => Integer(l * r),
-            "/" => Integer(l / r),
-            "<" => Boolean(l < r),
-            ">" => Boolean(l > r),
-            "==" => Boolean(l == r),
-            "!=" => Boolean(l != r),
-            _ => Null,
+            "+" => l.checked_add(r).map(Integer),
+            "-" => l.checked_sub(r).map(Integer),
+            "*" => l.checked_mul(r).map(Integer),
+            "/" | "%" if r == 0 => {
+                let message = if operator == "/" { "division by zero" } else { "modulo by zero" };
+                return Err(RuntimeError::new(ErrorKind::Arithmetic, message, None));
+            }
+            "/" => l.checked_div(r).map(Integer),
+            "%" => l.checked_rem(r).map(Integer),
+            // A negative exponent gives a float.
+            "**" => match u32::try_from(r) {
+                Ok(exp) => l.checked_pow(exp).map(Integer),
+                Err(_) if r < 0 => Some(Float((l as f64).powf(r as f64))),
+                Err(_) => None,
+            },
+            "&" => Some(Integer(l & r)),
+            "|" => Some(Integer(l | r)),
+            "^" => Some(Integer(l ^ r)),
+            "<<" => u32::try_from(r).ok().and_then(|n| l.checked_shl(n)).map(Integer),
+            ">>" => u32::try_from(r).ok().and_then(|n| l.checked_shr(n)).map(Integer),
+            "<" => Some(Boolean(l < r)),
+            ">" => Some(Boolean(l > r)),
+            "<=" => Some(Boolean(l <= r)),
+            ">=" => Some(Boolean(l >= r)),
+            "==" => Some(Boolean(l == r)),
+            "!=" => Some(Boolean(l != r)),
+            _ => return Err(operand_error(operator, &Integer(l), &Integer(r))),
+        }
+        .ok_or_else(overflow),
+
+        // Mixed int/float arithmetic promotes the integer side.
+        (Float(l), Float(r)) => eval_float_infix(operator, l, r),
+        (Integer(l), Float(r)) => eval_float_infix(operator, l as f64, r),
+        (Float(l), Integer(r)) => eval_float_infix(operator, l, r as f64),
+
+        (String(l), String(r)) => match operator {
+            "+" => Ok(String(l + &r)),
+            "<" => Ok(Boolean(l < r)),
+            ">" => Ok(Boolean(l > r)),
+            "<=" => Ok(Boolean(l <= r)),
+            ">=" => Ok(Boolean(l >= r)),
+            "==" => Ok(Boolean(l == r)),
+            "!=" => Ok(Boolean(l != r)),
+            _ => Err(operand_error(operator, &String(l), &String(r))),
         },
 
         (Boolean(l), Boolean(r)) => match operator {
-            "==" => Boolean(l == r),
-            "!=" => Boolean(l != r),
-            _ => Null,
+            "&" => Ok(Boolean(l & r)),
+            "|" => Ok(Boolean(l | r)),
+            "^" => Ok(Boolean(l ^ r)),
+            "==" => Ok(Boolean(l == r)),
+            "!=" => Ok(Boolean(l != r)),
+            _ => Err(operand_error(operator, &Boolean(l), &Boolean(r))),
         },
 
-        (l, r) => {
-            let eq = l == r;
-            match operator {
-                "==" => Boolean(eq),
-                "!=" => Boolean(!eq),
-                _ => Null,
-            }
-        }
+        // Any two values can be compared for equality.
+        (l, r) => match operator {
+            "==" => Ok(Boolean(l == r)),
+            "!=" => Ok(Boolean(l != r)),
+            _ => Err(operand_error(operator, &l, &r)),
+        },
     }
 }
+
+fn operand_error(operator: &str, left: &Object, right: &Object) -> RuntimeError {
+    RuntimeError::new(
+        ErrorKind::Type,
+        format!("cannot apply '{}' to {} and {}", operator, type_name(left), type_name(right)),
+        None,
+    )
+}
+
+fn overflow() -> RuntimeError {
+    RuntimeError::new(ErrorKind::Arithmetic, "integer overflow", None)
+}
+
+fn eval_float_infix(operator: &str, l: f64, r: f64) -> Result<Object, RuntimeError> {
+    use Object::*;
+    Ok(match operator {
+        "+" => Float(l + r),
+        "-" => Float(l - r),
+        "*" => Float(l * r),
+        "/" => Float(l / r),
+        "%" => Float(l % r),
+        "**" => Float(l.powf(r)),
+        "<" => Boolean(l < r),
+        ">" => Boolean(l > r),
+        "<=" => Boolean(l <= r),
+        ">=" => Boolean(l >= r),
+        "==" => Boolean(l == r),
+        "!=" => Boolean(l != r),
+        _ => return Err(operand_error(operator, &Float(l), &Float(r))),
+    })
+}
@@ -1,20 +1,56 @@
+use std::borrow::Cow;
+use std::fmt;
+
+// Tokens borrow their text from the source where they can. Only strings
+// with escape sequences need to own a decoded copy.
 #[derive(Debug, PartialEq, Clone)]
-pub enum Token {
+pub enum Token<'a> {
     Illegal,
     EOF,
 
     // Identifiers + literals
-    Ident(String),
-    Number(String),
+    Ident(Cow<'a, str>),
+    Int(i64),
+    Float(f64),
+    String(Cow<'a, str>),
+
+    // "total: ${a + b}" lexes as
+    // TemplateStart, StringFragment("total: "), InterpolationStart,
+    // a, +, b, InterpolationEnd, TemplateEnd
+    TemplateStart,
+    StringFragment(Cow<'a, str>),
+    InterpolationStart,
+    InterpolationEnd,
+    TemplateEnd,
 
     // Keywords
     Let,
+    Const,
     Fn,
     Return,
     True,
     False,
+    Null,
     If,
     Else,
+    While,
+    Loop,
+    For,
+    In,
+    Break,
+    Continue,
+    Match,
+    Struct,
+    Impl,
+    Trait,
+    Enum,
+    Import,
+    Export,
+    As,
+    Try,
+    Catch,
+    Finally,
+    Throw,
 
     // Operators
     Assign,
@@ -27,86 +63,754 @@ pub enum Token {
     GreaterThan,
     Equal,
     NotEqual,
-
+    LessEqual,
+    GreaterEqual,
+    Percent,
+    Power,
+    Ampersand,
+    Pipe,
+    Caret,
+    ShiftLeft,
+    ShiftRight,
+    And,
+    Or,
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+    FatArrow,
+    Arrow,
+    Dot,
+    DotDot,
 
     // Delimiters
     Comma,
     Semicolon,
+    Colon,
+    ColonColon,
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
+}
+
+impl Token<'_> {
+    // Detaches the token from the source text, e.g. to keep it in an error.
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::Ident(name) => Token::Ident(Cow::Owned(name.into_owned())),
+            Token::String(value) => Token::String(Cow::Owned(value.into_owned())),
+            Token::StringFragment(text) => Token::StringFragment(Cow::Owned(text.into_owned())),
+            Token::Illegal => Token::Illegal,
+            Token::EOF => Token::EOF,
+            Token::Int(value) => Token::Int(value),
+            Token::Float(value) => Token::Float(value),
+            Token::TemplateStart => Token::TemplateStart,
+            Token::InterpolationStart => Token::InterpolationStart,
+            Token::InterpolationEnd => Token::InterpolationEnd,
+            Token::TemplateEnd => Token::TemplateEnd,
+            Token::Let => Token::Let,
+            Token::Const => Token::Const,
+            Token::Fn => Token::Fn,
+            Token::Return => Token::Return,
+            Token::True => Token::True,
+            Token::False => Token::False,
+            Token::Null => Token::Null,
+            Token::If => Token::If,
+            Token::Else => Token::Else,
+            Token::While => Token::While,
+            Token::Loop => Token::Loop,
+            Token::For => Token::For,
+            Token::In => Token::In,
+            Token::Break => Token::Break,
+            Token::Continue => Token::Continue,
+            Token::Match => Token::Match,
+            Token::Struct => Token::Struct,
+            Token::Impl => Token::Impl,
+            Token::Trait => Token::Trait,
+            Token::Enum => Token::Enum,
+            Token::Import => Token::Import,
+            Token::Export => Token::Export,
+            Token::As => Token::As,
+            Token::Try => Token::Try,
+            Token::Catch => Token::Catch,
+            Token::Finally => Token::Finally,
+            Token::Throw => Token::Throw,
+            Token::Assign => Token::Assign,
+            Token::Plus => Token::Plus,
+            Token::Minus => Token::Minus,
+            Token::Asterisk => Token::Asterisk,
+            Token::Slash => Token::Slash,
+            Token::Bang => Token::Bang,
+            Token::LessThan => Token::LessThan,
+            Token::GreaterThan => Token::GreaterThan,
+            Token::Equal => Token::Equal,
+            Token::NotEqual => Token::NotEqual,
+            Token::LessEqual => Token::LessEqual,
+            Token::GreaterEqual => Token::GreaterEqual,
+            Token::Percent => Token::Percent,
+            Token::Power => Token::Power,
+            Token::Ampersand => Token::Ampersand,
+            Token::Pipe => Token::Pipe,
+            Token::Caret => Token::Caret,
+            Token::ShiftLeft => Token::ShiftLeft,
+            Token::ShiftRight => Token::ShiftRight,
+            Token::And => Token::And,
+            Token::Or => Token::Or,
+            Token::PlusAssign => Token::PlusAssign,
+            Token::MinusAssign => Token::MinusAssign,
+            Token::AsteriskAssign => Token::AsteriskAssign,
+            Token::SlashAssign => Token::SlashAssign,
+            Token::FatArrow => Token::FatArrow,
+            Token::Arrow => Token::Arrow,
+            Token::Dot => Token::Dot,
+            Token::DotDot => Token::DotDot,
+            Token::Comma => Token::Comma,
+            Token::Semicolon => Token::Semicolon,
+            Token::Colon => Token::Colon,
+            Token::ColonColon => Token::ColonColon,
+            Token::LParen => Token::LParen,
+            Token::RParen => Token::RParen,
+            Token::LBrace => Token::LBrace,
+            Token::RBrace => Token::RBrace,
+            Token::LBracket => Token::LBracket,
+            Token::RBracket => Token::RBracket,
+        }
+    }
+}
+
+// Prints a token the way it appears in source, for error messages.
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::Ident(name) => return write!(f, "{}", name),
+            Token::Int(value) => return write!(f, "{}", value),
+            Token::Float(value) => return write!(f, "{:?}", value),
+            Token::String(value) => return write!(f, "{:?}", value),
+            Token::StringFragment(text) => return write!(f, "{}", text),
+            Token::Illegal => "illegal token",
+            Token::EOF => "end of input",
+            Token::TemplateStart | Token::TemplateEnd => "\"",
+            Token::InterpolationStart => "${",
+            Token::InterpolationEnd => "}",
+            Token::Let => "let",
+            Token::Const => "const",
+            Token::Fn => "fn",
+            Token::Return => "return",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Null => "null",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::Loop => "loop",
+            Token::For => "for",
+            Token::In => "in",
+            Token::Break => "break",
+            Token::Continue => "continue",
+            Token::Match => "match",
+            Token::Struct => "struct",
+            Token::Impl => "impl",
+            Token::Trait => "trait",
+            Token::Enum => "enum",
+            Token::Import => "import",
+            Token::Export => "export",
+            Token::As => "as",
+            Token::Try => "try",
+            Token::Catch => "catch",
+            Token::Finally => "finally",
+            Token::Throw => "throw",
+            Token::Assign => "=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Asterisk => "*",
+            Token::Slash => "/",
+            Token::Bang => "!",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::LessEqual => "<=",
+            Token::GreaterEqual => ">=",
+            Token::Percent => "%",
+            Token::Power => "**",
+            Token::Ampersand => "&",
+            Token::Pipe => "|",
+            Token::Caret => "^",
+            Token::ShiftLeft => "<<",
+            Token::ShiftRight => ">>",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::PlusAssign => "+=",
+            Token::MinusAssign => "-=",
+            Token::AsteriskAssign => "*=",
+            Token::SlashAssign => "/=",
+            Token::FatArrow => "=>",
+            Token::Arrow => "->",
+            Token::Dot => ".",
+            Token::DotDot => "..",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::ColonColon => "::",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// Where a token came from in the source text.
+// start/end are byte offsets, line/column are 1-based and point at the first char.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+// A token together with the span it was read from.
+// `docs` holds the `///` doc comments written directly before the token,
+// one entry per line, so tooling can recover them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken<'a> {
+    pub token: Token<'a>,
+    pub span: Span,
+    pub docs: Vec<&'a str>,
+}
+
+// Lets callers compare against a bare Token: `tok == Token::Semicolon`.
+impl<'a> PartialEq<Token<'a>> for SpannedToken<'a> {
+    fn eq(&self, other: &Token<'a>) -> bool {
+        self.token == *other
+    }
+}
+
+// A problem found while lexing, e.g. an unterminated string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+// What the lexer is inside of. Interpolation re-enters normal lexing, so
+// strings and interpolations can nest: "a ${ "b ${c}" } d".
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    // Between the quotes of an interpolated string; `open` is the opening quote.
+    Template { open: Span },
+    // Inside `${ ... }`; `depth` counts unclosed '{' so the right '}' ends it.
+    Interpolation { depth: usize },
 }
 
-pub struct Lexer {
-    input: Vec<char>,
-    position: usize,      
-    read_position: usize, 
-    ch: char,             
+// Walks the UTF-8 bytes of the source directly; positions are byte offsets.
+pub struct Lexer<'a> {
+    input: &'a str,
+    position: usize,      // byte offset of ch
+    read_position: usize, // byte offset just past ch
+    ch: char,
+    line: usize,          // line of ch (1-based)
+    column: usize,        // column of ch (1-based)
+    errors: Vec<LexError>,
+    modes: Vec<Mode>,
 }
 
-impl Lexer {
-    pub fn new(input: &str) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
         let mut l = Lexer {
-            input: input.chars().collect(),
+            input,
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 1,
+            errors: vec![],
+            modes: vec![],
         };
-        l.read_char();  
+        l.read_char();
         l
     }
 
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = '\0';
-        } else {
-            self.ch = self.input[self.read_position];
+        // Step the line/column past the char we are leaving behind. Before the
+        // first char and at EOF there is nothing to step over.
+        if self.position < self.read_position {
+            if self.ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
         }
+
         self.position = self.read_position;
-        self.read_position += 1;
+        match self.input.as_bytes().get(self.position) {
+            None => self.ch = '\0',
+            Some(&b) if b.is_ascii() => {
+                self.ch = b as char;
+                self.read_position += 1;
+            }
+            Some(_) => {
+                let c = self.input[self.position..].chars().next().unwrap_or('\0');
+                self.ch = c;
+                self.read_position += c.len_utf8();
+            }
+        }
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.ch.is_whitespace() {
-            self.read_char();
+    // Fast path for the common case: steps over a run of ASCII bytes that
+    // match `accept` without decoding them one char at a time. `accept` must
+    // reject '\n' so the run stays on one line.
+    fn skip_ascii_while(&mut self, accept: fn(u8) -> bool) {
+        let bytes = self.input.as_bytes();
+        let mut end = self.position;
+        while end < bytes.len() && accept(bytes[end]) {
+            end += 1;
+        }
+        if end == self.position {
+            return;
+        }
+
+        self.column += end - self.position;
+        self.position = end;
+        self.read_position = end;
+        self.read_char(); // loads the char at `end` without stepping the column
+    }
+
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    fn error(&mut self, message: impl Into<String>, span: Span) {
+        self.errors.push(LexError { message: message.into(), span });
+    }
+
+    // Zero-width span at the current char; callers fill in `end`.
+    fn location(&self) -> Span {
+        Span { start: self.position, end: self.position, line: self.line, column: self.column }
+    }
+
+    fn at_eof(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    // Skips whitespace and comments, returning any doc comments passed on the way.
+    fn skip_trivia(&mut self) -> Vec<&'a str> {
+        let mut docs = vec![];
+
+        loop {
+            loop {
+                self.skip_ascii_while(|b| matches!(b, b' ' | b'\t' | b'\r'));
+                if !self.ch.is_whitespace() {
+                    break;
+                }
+                self.read_char();
+            }
+
+            match (self.ch, self.peek_char()) {
+                ('/', '/') => {
+                    // `///` is a doc comment, but `////...` is an ordinary one.
+                    let is_doc = self.peek_char_at(2) == '/' && self.peek_char_at(3) != '/';
+                    let text = self.read_line_comment();
+                    if is_doc {
+                        let text = &text[3..];
+                        docs.push(text.strip_prefix(' ').unwrap_or(text));
+                    }
+                }
+                ('/', '*') => self.skip_block_comment(),
+                _ => return docs,
+            }
         }
     }
 
-    fn read_identifier(&mut self) -> String {
+    fn read_line_comment(&mut self) -> &'a str {
         let start = self.position;
-        while self.ch.is_alphanumeric() || self.ch == '_' {
+        while !self.at_eof() && self.ch != '\n' {
             self.read_char();
         }
-        self.input[start..self.position].iter().collect()
+        &self.input[start..self.position]
+    }
+
+    // Block comments nest: `/* a /* b */ c */` is one comment.
+    fn skip_block_comment(&mut self) {
+        let open = self.location();
+        let mut depth = 0;
+
+        loop {
+            if self.at_eof() {
+                self.error("unterminated block comment", Span { end: self.position, ..open });
+                return;
+            }
+            match (self.ch, self.peek_char()) {
+                ('/', '*') => {
+                    depth += 1;
+                    self.read_char();
+                    self.read_char();
+                }
+                ('*', '/') => {
+                    depth -= 1;
+                    self.read_char();
+                    self.read_char();
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => self.read_char(),
+            }
+        }
     }
 
-    fn read_number(&mut self) -> String {
+    fn read_identifier(&mut self) -> &'a str {
         let start = self.position;
-        while self.ch.is_numeric() {
+        self.skip_ascii_while(|b| b.is_ascii_alphanumeric() || b == b'_');
+        while self.ch.is_alphanumeric() || self.ch == '_' {
             self.read_char();
         }
-        self.input[start..self.position].iter().collect()
+        &self.input[start..self.position]
     }
 
-    pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+    // Reads an integer or float literal, checking its value as it goes.
+    // Accepts 0x/0b/0o prefixes, `_` separators, fractions and exponents.
+    // A malformed or out-of-range literal is reported and lexes as Illegal.
+    fn read_number(&mut self) -> Token<'a> {
+        let start = self.location();
 
-        let tok = match self.ch {
-            '=' => {
-                if self.peek_char() == '=' {
-                    self.read_char();
+        let radix = match (self.ch, self.peek_char()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'b' | 'B') => 2,
+            ('0', 'o' | 'O') => 8,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.read_char();
+            self.read_char();
+            let digits_start = self.position;
+            self.read_digits(radix);
+            return self.finish_integer(digits_start, radix, start);
+        }
+
+        self.read_digits(10);
+        let mut is_float = false;
+
+        // Only a digit after '.' makes a fraction, so `1.x` and `1..2` still lex.
+        if self.ch == '.' && self.peek_char().is_ascii_digit() {
+            is_float = true;
+            self.read_char();
+            self.read_digits(10);
+        }
+
+        if matches!(self.ch, 'e' | 'E') {
+            let digit_at = if matches!(self.peek_char(), '+' | '-') { 2 } else { 1 };
+            if self.peek_char_at(digit_at).is_ascii_digit() {
+                is_float = true;
+                for _ in 0..digit_at {
                     self.read_char();
-                    Token::Equal
-                } else {
+                }
+                self.read_digits(10);
+            }
+        }
+
+        if !is_float {
+            return self.finish_integer(start.start, 10, start);
+        }
+
+        if !self.check_number_end(start) {
+            return Token::Illegal;
+        }
+        match without_separators(&self.input[start.start..self.position]).parse::<f64>() {
+            Ok(value) if value.is_finite() => Token::Float(value),
+            _ => {
+                self.error("float literal out of range", Span { end: self.position, ..start });
+                Token::Illegal
+            }
+        }
+    }
+
+    // Skips over digits of `radix` and `_` separators, reporting separators
+    // that do not sit between two digits.
+    fn read_digits(&mut self, radix: u32) {
+        let start = self.location();
+        let mut seen_digit = false;
+        let mut last_was_separator = false;
+
+        while self.ch == '_' || self.ch.is_digit(radix) {
+            if self.ch == '_' {
+                if !seen_digit || last_was_separator {
+                    let here = self.location();
+                    self.error("misplaced digit separator '_'", Span { end: here.start + 1, ..here });
+                }
+                last_was_separator = true;
+            } else {
+                seen_digit = true;
+                last_was_separator = false;
+            }
+            self.read_char();
+        }
+
+        if last_was_separator {
+            self.error("trailing digit separator '_'", Span { end: self.position, ..start });
+        }
+    }
+
+    // The digits run from `digits_start` to the current position.
+    fn finish_integer(&mut self, digits_start: usize, radix: u32, start: Span) -> Token<'a> {
+        if !self.check_number_end(start) {
+            return Token::Illegal;
+        }
+
+        let span = Span { end: self.position, ..start };
+        let digits = without_separators(&self.input[digits_start..self.position]);
+        if digits.is_empty() {
+            self.error("missing digits after number prefix", span);
+            return Token::Illegal;
+        }
+        match i64::from_str_radix(&digits, radix) {
+            Ok(value) => Token::Int(value),
+            Err(_) => {
+                self.error("integer literal out of range", span);
+                Token::Illegal
+            }
+        }
+    }
+
+    // A number must not run straight into letters or other digits: `12ab`, `0b102`.
+    fn check_number_end(&mut self, start: Span) -> bool {
+        if !(self.ch.is_alphanumeric() || self.ch == '_') {
+            return true;
+        }
+
+        let bad = self.ch;
+        while self.ch.is_alphanumeric() || self.ch == '_' {
+            self.read_char();
+        }
+        self.error(
+            format!("invalid character '{}' in number literal", bad),
+            Span { end: self.position, ..start },
+        );
+        false
+    }
+
+    fn read_string(&mut self) -> Token<'a> {
+        let open = self.location();
+        self.read_char(); // skip opening '"'
+
+        if self.has_interpolation() {
+            self.modes.push(Mode::Template { open });
+            return Token::TemplateStart;
+        }
+
+        let value = self.read_string_contents(false);
+        if self.at_eof() {
+            self.error("unterminated string literal", Span { end: self.position, ..open });
+        } else {
+            self.read_char(); // skip closing '"'
+        }
+        Token::String(value)
+    }
+
+    // Looks ahead from just after an opening quote for an unescaped `${`
+    // before the closing quote.
+    // Scanning bytes is safe here: UTF-8 continuation bytes are never ASCII.
+    fn has_interpolation(&self) -> bool {
+        let bytes = self.input.as_bytes();
+        let mut i = self.position;
+        while let Some(&b) = bytes.get(i) {
+            match b {
+                b'"' => return false,
+                b'\\' => i += 2,
+                b'$' if bytes.get(i + 1) == Some(&b'{') => return true,
+                _ => i += 1,
+            }
+        }
+        false
+    }
+
+    // Reads string text up to the closing quote or EOF, resolving escapes.
+    // In a template it also stops in front of `${`. The text is borrowed
+    // from the source unless an escape forces a decoded copy.
+    fn read_string_contents(&mut self, in_template: bool) -> Cow<'a, str> {
+        let input = self.input;
+        let start = self.position;
+        let mut decoded: Option<String> = None;
+
+        while !self.at_eof() && self.ch != '"' {
+            match self.ch {
+                '$' if in_template && self.peek_char() == '{' => break,
+                '\\' => {
+                    let value = decoded.get_or_insert_with(|| input[start..self.position].to_string());
+                    if let Some(c) = self.read_escape() {
+                        value.push(c);
+                    }
+                }
+                c => {
+                    if let Some(value) = decoded.as_mut() {
+                        value.push(c);
+                    }
                     self.read_char();
-                    Token::Assign
                 }
             }
-            '+' => { self.read_char(); Token::Plus }
-            '-' => { self.read_char(); Token::Minus }
-            '*' => { self.read_char(); Token::Asterisk }
-            '/' => { self.read_char(); Token::Slash }
+        }
+
+        match decoded {
+            Some(value) => Cow::Owned(value),
+            None => Cow::Borrowed(&input[start..self.position]),
+        }
+    }
+
+    // The next piece of an interpolated string: text, `${`, or the closing quote.
+    fn read_template_part(&mut self, open: Span) -> Token<'a> {
+        if self.at_eof() {
+            self.modes.pop();
+            self.error("unterminated string literal", Span { end: self.position, ..open });
+            return Token::TemplateEnd;
+        }
+
+        match self.ch {
+            '"' => {
+                self.read_char();
+                self.modes.pop();
+                Token::TemplateEnd
+            }
+            '$' if self.peek_char() == '{' => {
+                self.read_char();
+                self.read_char();
+                self.modes.push(Mode::Interpolation { depth: 0 });
+                Token::InterpolationStart
+            }
+            _ => Token::StringFragment(self.read_string_contents(true)),
+        }
+    }
+
+    // Reads one escape sequence starting at '\'. Bad escapes are reported and skipped.
+    fn read_escape(&mut self) -> Option<char> {
+        let start = self.location();
+        self.read_char(); // skip '\'
+
+        if self.at_eof() {
+            return None; // read_string reports the unterminated literal
+        }
+
+        let c = match self.ch {
+            'n' => '\n',
+            't' => '\t',
+            '"' => '"',
+            '\\' => '\\',
+            '$' => '$',
+            'u' => return self.read_unicode_escape(start),
+            other => {
+                self.read_char();
+                self.error(
+                    format!("unknown escape sequence '\\{}'", other),
+                    Span { end: self.position, ..start },
+                );
+                return None;
+            }
+        };
+        self.read_char();
+        Some(c)
+    }
+
+    // \u{1F600}: one to six hex digits naming a Unicode scalar value.
+    fn read_unicode_escape(&mut self, start: Span) -> Option<char> {
+        self.read_char(); // skip 'u'
+
+        if self.ch != '{' {
+            self.error("expected '{' after '\\u'", Span { end: self.position, ..start });
+            return None;
+        }
+        self.read_char();
+
+        let digits_start = self.position;
+        while self.ch.is_ascii_hexdigit() {
+            self.read_char();
+        }
+        let digits = &self.input[digits_start..self.position];
+
+        if self.ch != '}' {
+            self.error("unterminated unicode escape", Span { end: self.position, ..start });
+            return None;
+        }
+        self.read_char();
+
+        let span = Span { end: self.position, ..start };
+        if digits.is_empty() || digits.len() > 6 {
+            self.error("unicode escape must have 1 to 6 hex digits", span);
+            return None;
+        }
+        match u32::from_str_radix(digits, 16).ok().and_then(char::from_u32) {
+            Some(c) => Some(c),
+            None => {
+                self.error(format!("invalid unicode code point '{}'", digits), span);
+                None
+            }
+        }
+    }
+
+    pub fn next_token(&mut self) -> SpannedToken<'a> {
+        // Inside the quotes of a template, whitespace and `//` are string text.
+        if let Some(&Mode::Template { open }) = self.modes.last() {
+            let start = self.location();
+            let token = self.read_template_part(open);
+            return SpannedToken {
+                token,
+                span: Span { end: self.position, ..start },
+                docs: vec![],
+            };
+        }
+
+        let docs = self.skip_trivia();
+
+        let (start, line, column) = (self.position, self.line, self.column);
+        let token = self.read_token();
+
+        SpannedToken {
+            token,
+            span: Span { start, end: self.position, line, column },
+            docs,
+        }
+    }
+
+    // Reads a one-char operator, or a two-char one when the next char
+    // matches an entry in `longer`, e.g. '<' followed by '=' is LessEqual.
+    fn read_operator(&mut self, single: Token<'a>, longer: &[(char, Token<'a>)]) -> Token<'a> {
+        let next = self.peek_char();
+        self.read_char();
+        for (ch, token) in longer {
+            if *ch == next {
+                self.read_char();
+                return token.clone();
+            }
+        }
+        single
+    }
+
+    fn read_token(&mut self) -> Token<'a> {
+        let tok = match self.ch {
+            '=' => self.read_operator(Token::Assign, &[('=', Token::Equal), ('>', Token::FatArrow)]),
+            '+' => self.read_operator(Token::Plus, &[('=', Token::PlusAssign)]),
+            '-' => self.read_operator(Token::Minus, &[('=', Token::MinusAssign), ('>', Token::Arrow)]),
+            '*' => self.read_operator(Token::Asterisk, &[('*', Token::Power), ('=', Token::AsteriskAssign)]),
+            '/' => self.read_operator(Token::Slash, &[('=', Token::SlashAssign)]),
+            '%' => { self.read_char(); Token::Percent }
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
@@ -117,32 +821,96 @@ impl Lexer {
                     Token::Bang
                 }
             }
-            '<' => { self.read_char(); Token::LessThan }
-            '>' => { self.read_char(); Token::GreaterThan }
+            '<' => self.read_operator(Token::LessThan, &[('=', Token::LessEqual), ('<', Token::ShiftLeft)]),
+            '>' => self.read_operator(Token::GreaterThan, &[('=', Token::GreaterEqual), ('>', Token::ShiftRight)]),
+            '&' => self.read_operator(Token::Ampersand, &[('&', Token::And)]),
+            '|' => self.read_operator(Token::Pipe, &[('|', Token::Or)]),
+            '^' => { self.read_char(); Token::Caret }
             '(' => { self.read_char(); Token::LParen }
             ')' => { self.read_char(); Token::RParen }
-            '{' => { self.read_char(); Token::LBrace }
-            '}' => { self.read_char(); Token::RBrace }
+            '[' => { self.read_char(); Token::LBracket }
+            ']' => { self.read_char(); Token::RBracket }
+            ':' => self.read_operator(Token::Colon, &[(':', Token::ColonColon)]),
+            '.' => self.read_operator(Token::Dot, &[('.', Token::DotDot)]),
+            '{' => {
+                self.read_char();
+                if let Some(Mode::Interpolation { depth }) = self.modes.last_mut() {
+                    *depth += 1;
+                }
+                Token::LBrace
+            }
+            '}' => {
+                self.read_char();
+                match self.modes.last_mut() {
+                    Some(Mode::Interpolation { depth: 0 }) => {
+                        self.modes.pop();
+                        Token::InterpolationEnd
+                    }
+                    Some(Mode::Interpolation { depth }) => {
+                        *depth -= 1;
+                        Token::RBrace
+                    }
+                    _ => Token::RBrace,
+                }
+            }
             ',' => { self.read_char(); Token::Comma }
             ';' => { self.read_char(); Token::Semicolon }
-            '\0' => Token::EOF,
+            '"' => self.read_string(),
+            '\0' => {
+                // EOF inside `${` means the enclosing string never closed either.
+                if let Some(open) = self.modes.iter().rev().find_map(|mode| match mode {
+                    Mode::Template { open } => Some(*open),
+                    _ => None,
+                }) {
+                    self.modes.clear();
+                    self.error("unterminated string interpolation", Span { end: self.position, ..open });
+                }
+                Token::EOF
+            }
 
             _ => {
-                if self.ch.is_alphabetic() {
+                if self.ch.is_alphabetic() || self.ch == '_' {
                     let ident = self.read_identifier();
-                    return match ident.as_str() {
+                    return match ident {
                         "let" => Token::Let,
+                        "const" => Token::Const,
                         "fn" => Token::Fn,
                         "return" => Token::Return,
                         "true" => Token::True,
                         "false" => Token::False,
+                        "null" => Token::Null,
                         "if" => Token::If,
                         "else" => Token::Else,
-                        _ => Token::Ident(ident),
+                        "while" => Token::While,
+                        "loop" => Token::Loop,
+                        "for" => Token::For,
+                        "in" => Token::In,
+                        "break" => Token::Break,
+                        "continue" => Token::Continue,
+                        "match" => Token::Match,
+                        "struct" => Token::Struct,
+                        "impl" => Token::Impl,
+                        "trait" => Token::Trait,
+                        "enum" => Token::Enum,
+                        "import" => Token::Import,
+                        "export" => Token::Export,
+                        "as" => Token::As,
+                        "try" => Token::Try,
+                        "catch" => Token::Catch,
+                        "finally" => Token::Finally,
+                        "throw" => Token::Throw,
+                        _ => Token::Ident(Cow::Borrowed(ident)),
                     };
-                } else if self.ch.is_numeric() {
-                    return Token::Number(self.read_number());
+                } else if self.ch.is_ascii_digit() {
+                    return self.read_number();
                 } else {
+                    let start = self.location();
+                    let bad = self.ch;
+                    self.read_char();
+                    self.error(
+                        format!("unexpected character '{}'", bad),
+                        Span { end: self.position, ..start },
+                    );
                     Token::Illegal
                 }
             }
@@ -151,11 +919,39 @@ impl Lexer {
         tok
     }
     fn peek_char(&self) -> char {
-        if self.read_position >= self.input.len() {
-            '\0'
+        match self.input.as_bytes().get(self.read_position) {
+            None => '\0',
+            Some(&b) if b.is_ascii() => b as char,
+            Some(_) => self.input[self.read_position..].chars().next().unwrap_or('\0'),
+        }
+    }
+
+    // peek_char_at(1) is the same as peek_char().
+    fn peek_char_at(&self, n: usize) -> char {
+        self.input[self.position..].chars().nth(n).unwrap_or('\0')
+    }
+
+}
+
+// Yields tokens up to, but not including, EOF.
+impl<'a> Iterator for Lexer<'a> {
+    type Item = SpannedToken<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tok = self.next_token();
+        if tok == Token::EOF {
+            None
         } else {
-            self.input[self.read_position]
+            Some(tok)
         }
     }
+}
 
+// `1_000` -> `1000`; borrows when there is nothing to strip.
+fn without_separators(text: &str) -> Cow<'_, str> {
+    if text.contains('_') {
+        Cow::Owned(text.replace('_', ""))
+    } else {
+        Cow::Borrowed(text)
+    }
 }
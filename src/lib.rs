@@ -2,4 +2,5 @@ pub mod lexer;
 pub mod parser;
 pub mod ast;
 pub mod interpreter;
+pub mod checker;
 // This makes your modules visible to tests.
\ No newline at end of file
@@ -1,10 +1,45 @@
+use aion::checker::{Checker, Severity};
 use aion::lexer::Lexer;
 use aion::parser::Parser;
-use aion::interpreter::{eval_program, Environment};
+use aion::interpreter::{display, eval_program, run_file, Environment, Object};
 use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
+
+// Room for MAX_CALL_DEPTH nested calls, even in a debug build.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
 fn main() {
+    let interpreter = thread::Builder::new().stack_size(STACK_SIZE).spawn(run).unwrap();
+    if interpreter.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn run() {
+    // `aion path/to/main.aion` runs a file; with no arguments, start the REPL.
+    if let Some(path) = std::env::args().nth(1) {
+        let shown = match run_file(Path::new(&path)) {
+            Object::Error(err) => Err(*err),
+            Object::Null => return,
+            result => display(&result, None),
+        };
+        match shown {
+            Ok(shown) => println!("{}", shown),
+            Err(err) => {
+                eprintln!("{}: {}", err.kind, err);
+                for frame in err.trace() {
+                    eprintln!("    in {}", frame);
+                }
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     let mut env = Environment::new();
+    let mut checker = Checker::new();
     let mut input = String::new();
 
     println!("AION REPL — Type Ctrl+C to exit");
@@ -26,7 +61,31 @@ fn main() {
         let mut parser = Parser::new(Lexer::new(&input));
         let program = parser.parse_program();
 
+        let lexer_errors = parser.lexer_errors().iter().map(|err| (err.span, err.to_string()));
+        let mut syntax_errors: Vec<_> = lexer_errors.chain(parser.errors().iter().map(|err| (err.span, err.to_string()))).collect();
+        if !syntax_errors.is_empty() {
+            syntax_errors.sort_by_key(|(span, _)| span.start);
+            for (_, err) in syntax_errors {
+                println!("error: {}", err);
+            }
+            continue;
+        }
+
+        let type_errors = checker.check(&program);
+        for err in &type_errors {
+            match err.severity {
+                Severity::Error => println!("type error: {}", err),
+                Severity::Warning => println!("warning: {}", err),
+            }
+        }
+        if type_errors.iter().any(|err| err.severity == Severity::Error) {
+            continue;
+        }
+
         let result = eval_program(program, &mut env);
-        println!("{}", result);
+        match display(&result, None) {
+            Ok(shown) => println!("{}", shown),
+            Err(err) => println!("{}: {}", err.kind, err),
+        }
     }
 }
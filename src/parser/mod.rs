@@ -1,37 +1,174 @@
-use crate::lexer::{Lexer, Token};
-use crate::ast::{Statement, Expression};
+use crate::lexer::{LexError, Lexer, Span, SpannedToken, Token};
+use crate::ast::{
+    Block, Catch, Statement, Expression, InterpolationPart, MatchArm, Method, Pattern, TraitMethod, TypeAnnotation, TypeExpr,
+    TypeParameter, Variant,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::{discriminant, Discriminant};
 
-pub struct Parser {
-    lexer: Lexer,
-    current_token: Token,
-    next_token: Token,
+// Pratt parse functions. A prefix function starts on current_token; an
+// infix function starts on its operator with the left operand already parsed.
+pub type PrefixParseFn<'a> = fn(&mut Parser<'a>) -> Option<Expression>;
+pub type InfixParseFn<'a> = fn(&mut Parser<'a>, Expression) -> Option<Expression>;
+
+// Handlers are keyed by token kind, ignoring any payload, so
+// `Token::Ident("".into())` stands for every identifier.
+type TokenKind<'a> = Discriminant<Token<'a>>;
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    current_token: SpannedToken<'a>,
+    next_token: SpannedToken<'a>,
+    errors: Vec<ParseError>,
+    prefix_parse_fns: HashMap<TokenKind<'a>, PrefixParseFn<'a>>,
+    infix_parse_fns: HashMap<TokenKind<'a>, (Precedence, InfixParseFn<'a>)>,
+    // How many loops enclose the current token, so that a stray `break` or
+    // `continue` can be reported. Function bodies start again from zero.
+    loop_depth: usize,
+    // Off while parsing an `if`/`while`/`match` head or a `for` iterable,
+    // where `name {` starts the body rather than a struct literal.
+    struct_literals: bool,
+}
+
+// A syntax error: what the parser wanted, what it got instead, and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+    pub expected: Vec<String>,
+    pub found: Token<'static>,
 }
 
-#[derive(PartialEq, PartialOrd)]
-enum Precedence {
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Precedence {
     LOWEST,
+    ASSIGN,      // x = 1, x += 1 (right associative)
+    OR,          // ||
+    AND,         // &&
     EQUALS,      // == 
-    LESSGREATER, // < or >
+    LESSGREATER, // < > <= >=
+    BITOR,       // |
+    BITXOR,      // ^
+    BITAND,      // &
+    SHIFT,       // << >>
     SUM,         // + or -
-    PRODUCT,     // * or /
+    PRODUCT,     // * / %
+    POWER,       // ** (right associative)
     PREFIX,      // -X or !X
-    CALL,        // function calls: add(x, y)
+    CALL,        // function calls and indexing: add(x, y), a[i]
 }
 
 
-impl Parser {
-    pub fn new(mut lexer: Lexer) -> Self {
+impl<'a> Parser<'a> {
+    pub fn new(mut lexer: Lexer<'a>) -> Self {
         let current = lexer.next_token();
         let next = lexer.next_token();
 
-        Parser {
+        let mut parser = Parser {
             lexer,
             current_token: current,
             next_token: next,
+            errors: vec![],
+            prefix_parse_fns: HashMap::new(),
+            infix_parse_fns: HashMap::new(),
+            loop_depth: 0,
+            struct_literals: true,
+        };
+
+        parser.register_prefix(Token::Ident("".into()), Parser::parse_identifier);
+        parser.register_prefix(Token::Int(0), Parser::parse_literal);
+        parser.register_prefix(Token::Float(0.0), Parser::parse_literal);
+        parser.register_prefix(Token::String("".into()), Parser::parse_literal);
+        parser.register_prefix(Token::True, Parser::parse_literal);
+        parser.register_prefix(Token::False, Parser::parse_literal);
+        parser.register_prefix(Token::Null, Parser::parse_literal);
+        parser.register_prefix(Token::TemplateStart, Parser::parse_interpolated_string);
+        parser.register_prefix(Token::Bang, Parser::parse_prefix_expression);
+        parser.register_prefix(Token::Minus, Parser::parse_prefix_expression);
+        parser.register_prefix(Token::LParen, Parser::parse_grouped_expression);
+        parser.register_prefix(Token::LBracket, Parser::parse_array_literal);
+        parser.register_prefix(Token::LBrace, Parser::parse_hash_literal);
+        parser.register_prefix(Token::If, Parser::parse_if_expression);
+        parser.register_prefix(Token::Fn, Parser::parse_function_literal);
+        parser.register_prefix(Token::While, Parser::parse_while_expression);
+        parser.register_prefix(Token::Loop, Parser::parse_loop_expression);
+        parser.register_prefix(Token::For, Parser::parse_for_expression);
+        parser.register_prefix(Token::Match, Parser::parse_match_expression);
+        parser.register_prefix(Token::Try, Parser::parse_try_expression);
+
+        let binary = [
+            (Token::Or, Precedence::OR),
+            (Token::And, Precedence::AND),
+            (Token::Equal, Precedence::EQUALS),
+            (Token::NotEqual, Precedence::EQUALS),
+            (Token::LessThan, Precedence::LESSGREATER),
+            (Token::GreaterThan, Precedence::LESSGREATER),
+            (Token::LessEqual, Precedence::LESSGREATER),
+            (Token::GreaterEqual, Precedence::LESSGREATER),
+            (Token::Pipe, Precedence::BITOR),
+            (Token::Caret, Precedence::BITXOR),
+            (Token::Ampersand, Precedence::BITAND),
+            (Token::ShiftLeft, Precedence::SHIFT),
+            (Token::ShiftRight, Precedence::SHIFT),
+            (Token::Plus, Precedence::SUM),
+            (Token::Minus, Precedence::SUM),
+            (Token::Asterisk, Precedence::PRODUCT),
+            (Token::Slash, Precedence::PRODUCT),
+            (Token::Percent, Precedence::PRODUCT),
+            (Token::Power, Precedence::POWER),
+        ];
+        for (token, precedence) in binary {
+            parser.register_infix(token, precedence, Parser::parse_infix_expression);
+        }
+
+        parser.register_infix(Token::LParen, Precedence::CALL, Parser::parse_call_expression);
+        parser.register_infix(Token::LBracket, Precedence::CALL, Parser::parse_index_expression);
+        parser.register_infix(Token::Dot, Precedence::CALL, Parser::parse_field_expression);
+        parser.register_infix(Token::ColonColon, Precedence::CALL, Parser::parse_path_expression);
+
+        for token in [Token::Assign, Token::PlusAssign, Token::MinusAssign, Token::AsteriskAssign, Token::SlashAssign] {
+            parser.register_infix(token, Precedence::ASSIGN, Parser::parse_assign_expression);
         }
+
+        parser
+    }
+
+    // Makes `f` the handler for expressions that start with this kind of token.
+    pub fn register_prefix(&mut self, token: Token<'a>, f: PrefixParseFn<'a>) {
+        self.prefix_parse_fns.insert(discriminant(&token), f);
     }
 
-    fn advance_tokens(&mut self) {
+    // Makes `f` the handler for this kind of token between two operands.
+    pub fn register_infix(&mut self, token: Token<'a>, precedence: Precedence, f: InfixParseFn<'a>) {
+        self.infix_parse_fns.insert(discriminant(&token), (precedence, f));
+    }
+
+    pub fn current_token(&self) -> &SpannedToken<'a> {
+        &self.current_token
+    }
+
+    pub fn peek_token(&self) -> &SpannedToken<'a> {
+        &self.next_token
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    // Problems the lexer reported while producing tokens for this parser.
+    pub fn lexer_errors(&self) -> &[LexError] {
+        self.lexer.errors()
+    }
+
+    pub fn advance_tokens(&mut self) {
         self.current_token = std::mem::replace(&mut self.next_token, self.lexer.next_token());
     }
 
@@ -39,34 +176,104 @@ impl Parser {
         let mut statements = vec![];
 
         while self.current_token != Token::EOF {
-            if let Some(stmt) = self.parse_statement() {
-                statements.push(stmt);
+            match self.parse_statement() {
+                Some(stmt) => statements.push(stmt),
+                None => self.synchronize(),
+            }
+            if self.current_token != Token::EOF {
+                self.advance_tokens();
             }
-            self.advance_tokens();
         }
 
         statements
     }
 
+    // After an error, skip ahead to a `;` or `}` so parsing can resume at
+    // the next statement and report any further problems.
+    fn synchronize(&mut self) {
+        while !matches!(self.current_token.token, Token::Semicolon | Token::RBrace | Token::EOF) {
+            self.advance_tokens();
+        }
+    }
+
+    fn error_at(&mut self, tok: &SpannedToken, expected: &[&str], message: String) {
+        self.errors.push(ParseError {
+            message,
+            span: tok.span,
+            expected: expected.iter().map(|s| s.to_string()).collect(),
+            found: tok.token.clone().into_owned(),
+        });
+    }
+
+    // "expected ')', found ';'" about the next token.
+    fn peek_error(&mut self, expected: &[&str]) {
+        let tok = self.next_token.clone();
+        let message = format!("expected {}, found {}", expected.join(" or "), describe(&tok.token));
+        self.error_at(&tok, expected, message);
+    }
+
+    // Advances onto the next token if it is `expected`, else records an error.
+    pub fn expect_peek(&mut self, expected: Token<'static>) -> bool {
+        if self.next_token == expected {
+            self.advance_tokens();
+            true
+        } else {
+            self.peek_error(&[&format!("'{}'", expected)]);
+            false
+        }
+    }
+
     fn parse_statement(&mut self) -> Option<Statement> {
-        match self.current_token {
-            Token::Let => self.parse_let_statement(),
+        match self.current_token.token {
+            Token::Let | Token::Const => self.parse_let_statement(),
             Token::Return => self.parse_return_statement(),
+            Token::Throw => self.parse_throw_statement(),
+            Token::LBrace => self.parse_block().map(Statement::Block),
+            Token::Break | Token::Continue => self.parse_loop_control(),
+            Token::Struct => self.parse_struct_declaration(),
+            Token::Enum => self.parse_enum_declaration(),
+            Token::Impl => self.parse_impl(),
+            Token::Trait => self.parse_trait(),
+            Token::Fn if matches!(self.next_token.token, Token::Ident(_)) => self.parse_function_declaration(),
+            Token::Import => self.parse_import(),
+            Token::Export => self.parse_export(),
             _ => self.parse_expression_statement(),
         }
     }
 
 
+    // let x = ...; and const x = ...;
     fn parse_let_statement(&mut self) -> Option<Statement> {
+        let is_const = self.current_token == Token::Const;
+
+        let span = self.current_token.span;
+        if !is_const && matches!(self.next_token.token, Token::LBracket | Token::LBrace) {
+            self.advance_tokens();
+            return self.parse_let_pattern(span);
+        }
+
         // expect identifier next
-        let name = if let Token::Ident(ref ident) = self.next_token {
-            ident.clone()
+        let name = if let Token::Ident(ref ident) = self.next_token.token {
+            ident.to_string()
         } else {
+            self.peek_error(&["identifier"]);
             return None;
         };
 
         self.advance_tokens(); // move to identifier
-        self.advance_tokens(); // move to '='
+        // `let Shape::Rect(w, h) = ...` or `let Some(x) = ...`
+        if !is_const && matches!(self.next_token.token, Token::ColonColon | Token::LParen) {
+            return self.parse_let_pattern(span);
+        }
+        let annotation = if self.next_token == Token::Colon {
+            self.advance_tokens();
+            Some(self.parse_annotation()?)
+        } else {
+            None
+        };
+        if !self.expect_peek(Token::Assign) {
+            return None;
+        }
 
         // Now parse the expression after '='
         self.advance_tokens(); // move to first token of expression
@@ -77,104 +284,1175 @@ impl Parser {
             self.advance_tokens();
         }
 
-        Some(Statement::LetStatement { name, value })
+        if is_const {
+            Some(Statement::ConstStatement { name, annotation, value, span })
+        } else {
+            Some(Statement::LetStatement { name, annotation, value, span })
+        }
     }
 
+    // fn add(a, b) { ... } is `let add = fn(a, b) { ... };`.
+    fn parse_function_declaration(&mut self) -> Option<Statement> {
+        let span = self.current_token.span;
+        let name = self.expect_ident("function name")?;
+        let value = self.parse_function_literal()?;
+        Some(Statement::LetStatement { name, annotation: None, value, span })
+    }
 
-    fn get_precedence(token: &Token) -> Precedence {
-        match token {
-            Token::Equal | Token::NotEqual => Precedence::EQUALS,
-            Token::LessThan | Token::GreaterThan => Precedence::LESSGREATER,
-            Token::Plus | Token::Minus => Precedence::SUM,
-            Token::Asterisk | Token::Slash => Precedence::PRODUCT,
-            _ => Precedence::LOWEST,
-        }
+    // The type after a ':' or '->', which is current_token; leaves
+    // current_token on the type's last token.
+    fn parse_annotation(&mut self) -> Option<TypeAnnotation> {
+        let span = self.next_token.span;
+        self.advance_tokens();
+        let ty = self.parse_type()?;
+        Some(TypeAnnotation { ty, span })
     }
 
-    fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression> {
-        let mut left_exp = match self.current_token.clone() {
-            Token::Ident(ident) => {
-                Some(Expression::Identifier(ident))
+    // int, [int], {string: int}, fn(int) -> bool; current_token is its first token.
+    fn parse_type(&mut self) -> Option<TypeExpr> {
+        match &self.current_token.token {
+            Token::Ident(name) => Some(TypeExpr::Named(name.to_string())),
+            Token::Null => Some(TypeExpr::Named("null".to_string())),
+            Token::LBracket => {
+                self.advance_tokens();
+                let element = self.parse_type()?;
+                if !self.expect_peek(Token::RBracket) {
+                    return None;
+                }
+                Some(TypeExpr::Array(Box::new(element)))
             }
-            Token::Number(num) => {
-                Some(Expression::NumberLiteral(num.parse::<i64>().unwrap()))
+            Token::LBrace => {
+                self.advance_tokens();
+                let key = self.parse_type()?;
+                if !self.expect_peek(Token::Colon) {
+                    return None;
+                }
+                self.advance_tokens();
+                let value = self.parse_type()?;
+                if !self.expect_peek(Token::RBrace) {
+                    return None;
+                }
+                Some(TypeExpr::Hash(Box::new(key), Box::new(value)))
             }
-            Token::Bang | Token::Minus => {
-                self.parse_prefix_expression()
+            Token::Fn => {
+                if !self.expect_peek(Token::LParen) {
+                    return None;
+                }
+                let mut parameters = vec![];
+                while self.next_token != Token::RParen {
+                    self.advance_tokens();
+                    parameters.push(self.parse_type()?);
+                    if !self.expect_list_separator(Token::RParen) {
+                        return None;
+                    }
+                }
+                self.advance_tokens(); // move to ')'
+                // Without `->`, the function may return anything.
+                let ret = if self.next_token == Token::Arrow {
+                    self.advance_tokens();
+                    self.advance_tokens();
+                    self.parse_type()?
+                } else {
+                    TypeExpr::Named("any".to_string())
+                };
+                Some(TypeExpr::Function(parameters, Box::new(ret)))
             }
-            Token::LParen => {
-                self.advance_tokens(); // skip '('
-                let expr = self.parse_expression(Precedence::LOWEST);
-                self.expect_rparen();
-                expr
+            _ => {
+                let tok = self.current_token.clone();
+                let message = format!("expected type, found {}", describe(&tok.token));
+                self.error_at(&tok, &["type"], message);
+                None
             }
-            _ => return None,
+        }
+    }
+
+
+    // let [a, ..rest] = value; current_token is `let`.
+    // Called on the first token of the pattern; `span` is the `let`'s.
+    fn parse_let_pattern(&mut self, span: Span) -> Option<Statement> {
+        let pattern = self.parse_pattern()?;
+
+        if !self.expect_peek(Token::Assign) {
+            return None;
+        }
+        self.advance_tokens();
+        let value = self.parse_expression(Precedence::LOWEST)?;
+
+        if self.next_token == Token::Semicolon {
+            self.advance_tokens();
+        }
+
+        Some(Statement::LetPattern { pattern, value, span })
+    }
+
+    fn precedence_of(&self, token: &Token<'a>) -> Precedence {
+        self.infix_parse_fns
+            .get(&discriminant(token))
+            .map_or(Precedence::LOWEST, |(precedence, _)| *precedence)
+    }
+
+    pub fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression> {
+        let Some(prefix) = self.prefix_parse_fns.get(&discriminant(&self.current_token.token)).copied() else {
+            // The lexer has already reported what was wrong with an Illegal token.
+            if self.current_token != Token::Illegal {
+                let tok = self.current_token.clone();
+                let message = format!("expected expression, found {}", describe(&tok.token));
+                self.error_at(&tok, &["expression"], message);
+            }
+            return None;
         };
 
+        let mut left_exp = prefix(self)?;
+
         while self.next_token != Token::Semicolon 
-            && precedence < Self::get_precedence(&self.next_token)
+            && precedence < self.precedence_of(&self.next_token.token)
         {
-            match self.next_token.clone() {
-                Token::Plus | Token::Minus | Token::Asterisk | Token::Slash | Token::LessThan | Token::GreaterThan => {
+            let (_, infix) = self.infix_parse_fns[&discriminant(&self.next_token.token)];
+            self.advance_tokens();
+            left_exp = infix(self, left_exp)?;
+        }
+
+        Some(left_exp)
+    }
+
+    fn parse_identifier(&mut self) -> Option<Expression> {
+        let Token::Ident(name) = &self.current_token.token else {
+            return None;
+        };
+        let name = name.to_string();
+
+        if self.struct_literals && self.next_token == Token::LBrace {
+            return self.parse_struct_literal(name);
+        }
+        Some(Expression::Identifier { name, span: self.current_token.span })
+    }
+
+    // An expression in a head such as `if <condition> {`.
+    fn parse_condition(&mut self) -> Option<Expression> {
+        self.with_struct_literals(false, |parser| parser.parse_expression(Precedence::LOWEST))
+    }
+
+    // An expression inside brackets, where struct literals are always fine.
+    fn parse_nested_expression(&mut self) -> Option<Expression> {
+        self.with_struct_literals(true, |parser| parser.parse_expression(Precedence::LOWEST))
+    }
+
+    fn with_struct_literals<T>(&mut self, allowed: bool, parse: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = parse(self);
+        self.struct_literals = outer;
+        result
+    }
+
+    // Point { x: 1, y }: current_token is the type name.
+    fn parse_struct_literal(&mut self, name: String) -> Option<Expression> {
+        let span = self.current_token.span;
+        self.advance_tokens(); // move to '{'
+
+        let mut fields = vec![];
+        while self.next_token != Token::RBrace {
+            let field = self.expect_ident("field name")?;
+
+            let value = if self.next_token == Token::Colon {
+                self.advance_tokens();
+                self.advance_tokens();
+                self.parse_nested_expression()?
+            } else {
+                Expression::Identifier { name: field.clone(), span: self.current_token.span }
+            };
+            fields.push((field, value));
+
+            if !self.expect_list_separator(Token::RBrace) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Expression::StructLiteral { name, fields, span })
+    }
+
+    // p.x: current_token is '.'.
+    fn parse_field_expression(&mut self, object: Expression) -> Option<Expression> {
+        let span = self.current_token.span;
+        let field = self.expect_ident("field name")?;
+
+        Some(Expression::Field {
+            object: Box::new(object),
+            field,
+            span,
+        })
+    }
+
+    // Shape::Circle: current_token is '::'.
+    fn parse_path_expression(&mut self, object: Expression) -> Option<Expression> {
+        let span = self.current_token.span;
+        let member = self.expect_ident("name")?;
+
+        Some(Expression::Path {
+            object: Box::new(object),
+            member,
+            span,
+        })
+    }
+
+    // Advances onto the next token if it is an identifier and returns its
+    // name, else records an error that an `expected` was wanted.
+    fn expect_ident(&mut self, expected: &str) -> Option<String> {
+        let Token::Ident(name) = &self.next_token.token else {
+            self.peek_error(&[expected]);
+            return None;
+        };
+        let name = name.to_string();
+        self.advance_tokens();
+        Some(name)
+    }
+
+    // After a list item: steps over a ',' or stops before `end`.
+    fn expect_list_separator(&mut self, end: Token<'static>) -> bool {
+        if self.next_token == Token::Comma {
+            self.advance_tokens();
+            true
+        } else if self.next_token == end {
+            true
+        } else {
+            self.peek_error(&["','", &format!("'{}'", end)]);
+            false
+        }
+    }
+
+    // import "lib/math.aion" as math;
+    fn parse_import(&mut self) -> Option<Statement> {
+        let span = self.current_token.span;
+        let Token::String(path) = &self.next_token.token else {
+            self.peek_error(&["module path"]);
+            return None;
+        };
+        let path = path.to_string();
+        self.advance_tokens();
+
+        if !self.expect_peek(Token::As) {
+            return None;
+        }
+        let alias = self.expect_ident("module name")?;
+
+        if self.next_token == Token::Semicolon {
+            self.advance_tokens();
+        }
+
+        Some(Statement::Import { path, alias, span })
+    }
+
+    // export let x = 1; export fn f() { ... }; export struct Point { x, y }
+    fn parse_export(&mut self) -> Option<Statement> {
+        let is_declaration = matches!(
+            self.next_token.token,
+            Token::Let | Token::Const | Token::Fn | Token::Struct | Token::Enum | Token::Trait
+        );
+        if !is_declaration {
+            self.peek_error(&["'let'", "'const'", "'fn'", "'struct'", "'enum'", "'trait'"]);
+            return None;
+        }
+        self.advance_tokens();
+
+        let tok = self.current_token.clone();
+        let statement = self.parse_statement()?;
+        if let Statement::LetPattern { .. } = statement {
+            self.error_at(&tok, &[], "cannot export a destructuring let".to_string());
+            return None;
+        }
+
+        Some(Statement::Export(Box::new(statement)))
+    }
+
+    // struct Point { x, y }
+    fn parse_struct_declaration(&mut self) -> Option<Statement> {
+        let name = self.expect_ident("struct name")?;
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let mut fields: Vec<String> = vec![];
+        while self.next_token != Token::RBrace {
+            let field = self.expect_ident("field name")?;
+            if fields.contains(&field) {
+                let tok = self.current_token.clone();
+                self.error_at(&tok, &[], format!("field '{}' is declared twice", field));
+                return None;
+            }
+            fields.push(field);
+
+            if !self.expect_list_separator(Token::RBrace) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Statement::StructDeclaration { name, fields })
+    }
+
+    // enum Shape { Circle(r), Rect(w, h), Empty }
+    fn parse_enum_declaration(&mut self) -> Option<Statement> {
+        let name = self.expect_ident("enum name")?;
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let mut variants: Vec<Variant> = vec![];
+        while self.next_token != Token::RBrace {
+            let variant = self.expect_ident("variant name")?;
+            if variants.iter().any(|v| v.name == variant) {
+                let tok = self.current_token.clone();
+                self.error_at(&tok, &[], format!("variant '{}' is declared twice", variant));
+                return None;
+            }
+
+            let fields = if self.next_token == Token::LParen {
+                self.advance_tokens();
+                self.parse_parameters()?
+            } else {
+                vec![]
+            };
+            variants.push(Variant { name: variant, fields });
+
+            if !self.expect_list_separator(Token::RBrace) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Statement::EnumDeclaration { name, variants })
+    }
+
+    // impl Point { fn name(self, ...) { ... } ... }, or
+    // impl Show for Point { ... } to implement a trait.
+    fn parse_impl(&mut self) -> Option<Statement> {
+        let span = self.current_token.span;
+        let mut type_name = self.expect_ident("type name")?;
+        let mut trait_name = None;
+        if self.next_token == Token::For {
+            self.advance_tokens();
+            trait_name = Some(std::mem::replace(&mut type_name, self.expect_ident("type name")?));
+        }
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let mut methods = vec![];
+        while self.next_token != Token::RBrace {
+            let signature = self.parse_method_signature()?;
+            let TraitMethod { name, type_parameters, parameters, parameter_types, return_type, .. } = signature;
+            let body = self.parse_function_body()?;
+            methods.push(Method { name, type_parameters, parameters, parameter_types, return_type, body });
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Statement::Impl { type_name, trait_name, methods, span })
+    }
+
+    // trait Show { fn show(self); fn print(self) { ... } }
+    fn parse_trait(&mut self) -> Option<Statement> {
+        let span = self.current_token.span;
+        let name = self.expect_ident("trait name")?;
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let mut methods = vec![];
+        while self.next_token != Token::RBrace {
+            let mut method = self.parse_method_signature()?;
+            if self.next_token == Token::Semicolon {
+                self.advance_tokens();
+            } else {
+                method.default = Some(self.parse_function_body()?);
+            }
+            methods.push(method);
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Statement::Trait { name, methods, span })
+    }
+
+    // `fn name<T>(self, ...) -> type` in an impl or trait, which next_token
+    // should start, as a method without a body yet. Leaves current_token on
+    // its last token.
+    fn parse_method_signature(&mut self) -> Option<TraitMethod> {
+        if !self.expect_peek(Token::Fn) {
+            return None;
+        }
+        let name = self.expect_ident("method name")?;
+        let type_parameters = self.parse_type_parameters()?;
+        if !self.expect_peek(Token::LParen) {
+            return None;
+        }
+        let (parameters, parameter_types) = self.parse_typed_parameters()?;
+        let return_type = self.parse_return_type()?;
+        Some(TraitMethod { name, type_parameters, parameters, parameter_types, return_type, default: None })
+    }
+
+    fn parse_literal(&mut self) -> Option<Expression> {
+        match &self.current_token.token {
+            Token::Int(value) => Some(Expression::NumberLiteral(*value)),
+            Token::Float(value) => Some(Expression::FloatLiteral(*value)),
+            Token::String(value) => Some(Expression::StringLiteral(value.to_string())),
+            Token::True => Some(Expression::BooleanLiteral(true)),
+            Token::False => Some(Expression::BooleanLiteral(false)),
+            Token::Null => Some(Expression::NullLiteral),
+            _ => None,
+        }
+    }
+
+    fn parse_grouped_expression(&mut self) -> Option<Expression> {
+        self.advance_tokens(); // skip '('
+        let expr = self.parse_nested_expression()?;
+        if !self.expect_peek(Token::RParen) {
+            return None;
+        }
+        Some(expr)
+    }
+
+    // fn(a, b) { ... } or fn<T: Show>(a: T) { ... }
+    fn parse_function_literal(&mut self) -> Option<Expression> {
+        let type_parameters = self.parse_type_parameters()?;
+        if !self.expect_peek(Token::LParen) {
+            return None;
+        }
+        let (parameters, parameter_types) = self.parse_typed_parameters()?;
+        let return_type = self.parse_return_type()?;
+        let body = self.parse_function_body()?;
+
+        Some(Expression::FunctionLiteral { type_parameters, parameters, parameter_types, return_type, body })
+    }
+
+    // An optional `<T: Show + Eq, U>` before a parameter list. The outer
+    // Option is None on a parse error.
+    fn parse_type_parameters(&mut self) -> Option<Vec<TypeParameter>> {
+        let mut type_parameters = vec![];
+        if self.next_token != Token::LessThan {
+            return Some(type_parameters);
+        }
+        self.advance_tokens();
+
+        while self.next_token != Token::GreaterThan {
+            let span = self.next_token.span;
+            let name = self.expect_ident("type parameter")?;
+            if type_parameters.iter().any(|parameter: &TypeParameter| parameter.name == name) {
+                let tok = self.current_token.clone();
+                self.error_at(&tok, &[], format!("type parameter '{}' is declared twice", name));
+                return None;
+            }
+
+            let mut bounds = vec![];
+            if self.next_token == Token::Colon {
+                self.advance_tokens();
+                bounds.push(self.expect_ident("trait name")?);
+                while self.next_token == Token::Plus {
                     self.advance_tokens();
-                    left_exp = Some(self.parse_infix_expression(left_exp.unwrap()));
+                    bounds.push(self.expect_ident("trait name")?);
+                }
+            }
+            type_parameters.push(TypeParameter { name, bounds, span });
+
+            if !self.expect_list_separator(Token::GreaterThan) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to '>'
+
+        Some(type_parameters)
+    }
+
+    // A function's parameters, each with an optional `: type`. current_token
+    // is '('; leaves current_token on ')'.
+    fn parse_typed_parameters(&mut self) -> Option<(Vec<String>, Vec<Option<TypeAnnotation>>)> {
+        let mut parameters = vec![];
+        let mut types = vec![];
+
+        while self.next_token != Token::RParen {
+            parameters.push(self.expect_ident("parameter name")?);
+            types.push(if self.next_token == Token::Colon {
+                self.advance_tokens();
+                Some(self.parse_annotation()?)
+            } else {
+                None
+            });
+
+            if !self.expect_list_separator(Token::RParen) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to ')'
+
+        Some((parameters, types))
+    }
+
+    // An optional `-> type` after a parameter list. The outer Option is
+    // None on a parse error.
+    fn parse_return_type(&mut self) -> Option<Option<TypeAnnotation>> {
+        if self.next_token != Token::Arrow {
+            return Some(None);
+        }
+        self.advance_tokens();
+        self.parse_annotation().map(Some)
+    }
+
+    // The `{ ... }` after a parameter list, which next_token should open.
+    fn parse_function_body(&mut self) -> Option<Block> {
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        // A `break` in the body cannot reach a loop around the function.
+        let outer_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.parse_block();
+        self.loop_depth = outer_depth;
+        body
+    }
+
+    // current_token is '('; leaves current_token on ')'.
+    fn parse_parameters(&mut self) -> Option<Vec<String>> {
+        let mut parameters = vec![];
+
+        if self.next_token == Token::RParen {
+            self.advance_tokens();
+            return Some(parameters);
+        }
+
+        loop {
+            match &self.next_token.token {
+                Token::Ident(name) => parameters.push(name.to_string()),
+                _ => {
+                    self.peek_error(&["parameter name"]);
+                    return None;
                 }
-                _ => return left_exp,
             }
+            self.advance_tokens();
+
+            if self.next_token != Token::Comma {
+                break;
+            }
+            self.advance_tokens();
         }
 
-        left_exp
+        if !self.expect_peek(Token::RParen) {
+            return None;
+        }
+        Some(parameters)
+    }
+
+    // f(x, y): current_token is '('.
+    fn parse_call_expression(&mut self, function: Expression) -> Option<Expression> {
+        let span = self.current_token.span;
+        let arguments = self.parse_expression_list(Token::RParen)?;
+
+        Some(Expression::Call {
+            function: Box::new(function),
+            arguments,
+            span,
+        })
+    }
+
+    // [1, 2, 3]
+    fn parse_array_literal(&mut self) -> Option<Expression> {
+        let elements = self.parse_expression_list(Token::RBracket)?;
+        Some(Expression::ArrayLiteral(elements))
+    }
+
+    // {key: value, ...}. Only reached in expression position: a statement
+    // that starts with '{' is a block.
+    fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let mut pairs = vec![];
+
+        while self.next_token != Token::RBrace {
+            self.advance_tokens();
+            let span = self.current_token.span;
+            let key = self.parse_nested_expression()?;
+            if !self.expect_peek(Token::Colon) {
+                return None;
+            }
+            self.advance_tokens();
+            let value = self.parse_nested_expression()?;
+            pairs.push((key, value, span));
+
+            if !self.expect_list_separator(Token::RBrace) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Expression::HashLiteral(pairs))
+    }
+
+    // a[i] or a[start:end]: current_token is '['.
+    fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+        let span = self.current_token.span;
+
+        if self.next_token == Token::Colon {
+            return self.parse_slice(left, None, span);
+        }
+
+        self.advance_tokens();
+        let index = self.parse_nested_expression()?;
+
+        if self.next_token == Token::Colon {
+            return self.parse_slice(left, Some(Box::new(index)), span);
+        }
+        if !self.expect_peek(Token::RBracket) {
+            return None;
+        }
+
+        Some(Expression::Index {
+            left: Box::new(left),
+            index: Box::new(index),
+            span,
+        })
+    }
+
+    // The rest of a[start:end]; next_token is the ':'.
+    fn parse_slice(&mut self, left: Expression, start: Option<Box<Expression>>, span: Span) -> Option<Expression> {
+        self.advance_tokens(); // move to ':'
+
+        let end = if self.next_token == Token::RBracket {
+            None
+        } else {
+            self.advance_tokens();
+            Some(Box::new(self.parse_nested_expression()?))
+        };
+
+        if !self.expect_peek(Token::RBracket) {
+            return None;
+        }
+
+        Some(Expression::Slice {
+            left: Box::new(left),
+            start,
+            end,
+            span,
+        })
+    }
+
+    // Comma-separated expressions up to `end`, starting with current_token on
+    // the opening delimiter and leaving it on `end`.
+    fn parse_expression_list(&mut self, end: Token<'static>) -> Option<Vec<Expression>> {
+        let mut list = vec![];
+
+        if self.next_token == end {
+            self.advance_tokens();
+            return Some(list);
+        }
+
+        self.advance_tokens();
+        list.push(self.parse_nested_expression()?);
+
+        while self.next_token == Token::Comma {
+            self.advance_tokens();
+            self.advance_tokens();
+            list.push(self.parse_nested_expression()?);
+        }
+
+        if !self.expect_peek(end) {
+            return None;
+        }
+        Some(list)
+    }
+
+    // if <condition> { ... } [else { ... } | else if ...]
+    fn parse_if_expression(&mut self) -> Option<Expression> {
+        self.advance_tokens(); // skip 'if'
+        let condition = self.parse_condition()?;
+
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        let consequence = self.parse_block()?;
+
+        let alternative = if self.next_token == Token::Else {
+            self.advance_tokens();
+            if self.next_token == Token::If {
+                self.advance_tokens();
+                let nested = self.parse_if_expression()?;
+                Some(vec![Statement::ExpressionStatement(nested)])
+            } else {
+                if !self.expect_peek(Token::LBrace) {
+                    return None;
+                }
+                Some(self.parse_block()?)
+            }
+        } else {
+            None
+        };
+
+        Some(Expression::If {
+            condition: Box::new(condition),
+            consequence,
+            alternative,
+        })
+    }
+
+    // try { ... } [catch [name] { ... }] [finally { ... }]
+    fn parse_try_expression(&mut self) -> Option<Expression> {
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        let body = self.parse_block()?;
+
+        let catch = if self.next_token == Token::Catch {
+            self.advance_tokens();
+            let name = match &self.next_token.token {
+                Token::Ident(_) => self.expect_ident("error name"),
+                _ => None,
+            };
+            if !self.expect_peek(Token::LBrace) {
+                return None;
+            }
+            Some(Catch { name, body: self.parse_block()? })
+        } else {
+            None
+        };
+
+        let finally = if self.next_token == Token::Finally {
+            self.advance_tokens();
+            if !self.expect_peek(Token::LBrace) {
+                return None;
+            }
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+
+        if catch.is_none() && finally.is_none() {
+            self.peek_error(&["'catch'", "'finally'"]);
+            return None;
+        }
+
+        Some(Expression::Try { body, catch, finally })
+    }
+
+    // match <subject> { <pattern> [if <guard>] => <body>, ... }
+    // A body is an expression or a block; a comma is only needed after an
+    // expression.
+    fn parse_match_expression(&mut self) -> Option<Expression> {
+        let span = self.current_token.span;
+
+        self.advance_tokens(); // skip 'match'
+        let subject = self.parse_condition()?;
+
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+
+        let mut arms = vec![];
+        while self.next_token != Token::RBrace {
+            self.advance_tokens();
+            let pattern = self.parse_pattern()?;
+
+            let guard = if self.next_token == Token::If {
+                self.advance_tokens();
+                self.advance_tokens();
+                Some(self.parse_expression(Precedence::LOWEST)?)
+            } else {
+                None
+            };
+
+            if !self.expect_peek(Token::FatArrow) {
+                return None;
+            }
+            self.advance_tokens();
+
+            let body = if self.current_token == Token::LBrace {
+                self.parse_block()?
+            } else {
+                vec![Statement::ExpressionStatement(self.parse_expression(Precedence::LOWEST)?)]
+            };
+            let is_block = self.current_token == Token::RBrace;
+
+            arms.push(MatchArm { pattern, guard, body });
+
+            match self.next_token.token {
+                Token::Comma => self.advance_tokens(),
+                Token::RBrace => {}
+                _ if is_block => {}
+                _ => {
+                    self.peek_error(&["','", "'}'"]);
+                    return None;
+                }
+            }
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Expression::Match {
+            subject: Box::new(subject),
+            arms,
+            span,
+        })
+    }
+
+    // Starts on the pattern's first token and leaves current_token on its last.
+    fn parse_pattern(&mut self) -> Option<Pattern> {
+        match &self.current_token.token {
+            Token::Ident(name) if name == "_" => Some(Pattern::Wildcard),
+            Token::Ident(_) if matches!(self.next_token.token, Token::ColonColon | Token::LParen) => {
+                self.parse_variant_pattern()
+            }
+            Token::Ident(name) => Some(Pattern::Binding(name.to_string())),
+            Token::Int(_) | Token::Float(_) | Token::String(_) | Token::True | Token::False | Token::Null => {
+                self.parse_literal().map(Pattern::Literal)
+            }
+            // Negative numbers, e.g. `-1 => ...`.
+            Token::Minus if matches!(self.next_token.token, Token::Int(_) | Token::Float(_)) => {
+                let span = self.current_token.span;
+                self.advance_tokens();
+                let right = self.parse_literal()?;
+                Some(Pattern::Literal(Expression::Prefix {
+                    operator: "-".to_string(),
+                    right: Box::new(right),
+                    span,
+                }))
+            }
+            Token::LBracket => self.parse_array_pattern(),
+            Token::LBrace => self.parse_hash_pattern(),
+            _ => {
+                let tok = self.current_token.clone();
+                let message = format!("expected pattern, found {}", describe(&tok.token));
+                self.error_at(&tok, &["pattern"], message);
+                None
+            }
+        }
+    }
+
+    // Shape::Rect(w, h), Some(x), Shape::Empty
+    fn parse_variant_pattern(&mut self) -> Option<Pattern> {
+        let span = self.current_token.span;
+        let Token::Ident(first) = &self.current_token.token else {
+            return None;
+        };
+        let first = first.to_string();
+
+        let (enum_name, variant) = if self.next_token == Token::ColonColon {
+            self.advance_tokens();
+            (Some(first), self.expect_ident("variant name")?)
+        } else {
+            (None, first)
+        };
+
+        let mut fields = vec![];
+        if self.next_token == Token::LParen {
+            self.advance_tokens();
+            while self.next_token != Token::RParen {
+                self.advance_tokens();
+                fields.push(self.parse_pattern()?);
+                if !self.expect_list_separator(Token::RParen) {
+                    return None;
+                }
+            }
+            self.advance_tokens(); // move to ')'
+        }
+
+        Some(Pattern::Variant { enum_name, variant, fields, span })
+    }
+
+    // [a, b], [first, ..rest], [.., last]
+    fn parse_array_pattern(&mut self) -> Option<Pattern> {
+        let mut elements = vec![];
+        let mut has_rest = false;
+
+        while self.next_token != Token::RBracket {
+            self.advance_tokens();
+
+            let element = if self.current_token == Token::DotDot {
+                let tok = self.current_token.clone();
+                if has_rest {
+                    self.error_at(&tok, &[], "only one '..' is allowed in an array pattern".to_string());
+                    return None;
+                }
+                has_rest = true;
+
+                match &self.next_token.token {
+                    Token::Ident(name) if name != "_" => {
+                        let name = name.to_string();
+                        self.advance_tokens();
+                        Pattern::Rest(Some(name))
+                    }
+                    _ => Pattern::Rest(None),
+                }
+            } else {
+                self.parse_pattern()?
+            };
+            elements.push(element);
+
+            if !self.expect_list_separator(Token::RBracket) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to ']'
+
+        Some(Pattern::Array(elements))
+    }
+
+    // {"key": pattern, 1: pattern}
+    fn parse_hash_pattern(&mut self) -> Option<Pattern> {
+        let mut pairs = vec![];
+
+        while self.next_token != Token::RBrace {
+            self.advance_tokens();
+
+            let key = match self.current_token.token {
+                Token::Int(_) | Token::String(_) | Token::True | Token::False => self.parse_literal()?,
+                _ => {
+                    let tok = self.current_token.clone();
+                    let message = format!("expected hash key, found {}", describe(&tok.token));
+                    self.error_at(&tok, &["hash key"], message);
+                    return None;
+                }
+            };
+
+            if !self.expect_peek(Token::Colon) {
+                return None;
+            }
+            self.advance_tokens();
+            pairs.push((key, self.parse_pattern()?));
+
+            if !self.expect_list_separator(Token::RBrace) {
+                return None;
+            }
+        }
+        self.advance_tokens(); // move to '}'
+
+        Some(Pattern::Hash(pairs))
+    }
+
+    // while <condition> { ... }
+    fn parse_while_expression(&mut self) -> Option<Expression> {
+        self.advance_tokens(); // skip 'while'
+        let condition = self.parse_condition()?;
+
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+
+        Some(Expression::While {
+            condition: Box::new(condition),
+            body,
+        })
+    }
+
+    // loop { ... }
+    fn parse_loop_expression(&mut self) -> Option<Expression> {
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+
+        Some(Expression::Loop { body })
+    }
+
+    // for <name> in <iterable> { ... }
+    fn parse_for_expression(&mut self) -> Option<Expression> {
+        let span = self.current_token.span;
+
+        let variable = if let Token::Ident(ref ident) = self.next_token.token {
+            ident.to_string()
+        } else {
+            self.peek_error(&["loop variable"]);
+            return None;
+        };
+        self.advance_tokens();
+
+        if !self.expect_peek(Token::In) {
+            return None;
+        }
+        self.advance_tokens();
+        let iterable = self.parse_condition()?;
+
+        if !self.expect_peek(Token::LBrace) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+
+        Some(Expression::For {
+            variable,
+            iterable: Box::new(iterable),
+            body,
+            span,
+        })
+    }
+
+    fn parse_loop_body(&mut self) -> Option<Block> {
+        self.loop_depth += 1;
+        let body = self.parse_block();
+        self.loop_depth -= 1;
+        body
+    }
+
+    // break; break value; continue;
+    fn parse_loop_control(&mut self) -> Option<Statement> {
+        let keyword = self.current_token.clone();
+
+        if self.loop_depth == 0 {
+            let message = format!("'{}' outside of a loop", keyword.token);
+            self.error_at(&keyword, &[], message);
+            return None;
+        }
+
+        let value = match keyword.token {
+            Token::Break if !matches!(self.next_token.token, Token::Semicolon | Token::RBrace | Token::EOF) => {
+                self.advance_tokens();
+                Some(self.parse_expression(Precedence::LOWEST)?)
+            }
+            _ => None,
+        };
+
+        if self.next_token == Token::Semicolon {
+            self.advance_tokens();
+        }
+
+        match keyword.token {
+            Token::Break => Some(Statement::Break { value }),
+            _ => Some(Statement::Continue),
+        }
+    }
+
+    // current_token is '{'; leaves current_token on the matching '}'.
+    fn parse_block(&mut self) -> Option<Block> {
+        self.with_struct_literals(true, Parser::parse_block_statements)
+    }
+
+    fn parse_block_statements(&mut self) -> Option<Block> {
+        let mut statements = vec![];
+        self.advance_tokens(); // skip '{'
+
+        while self.current_token != Token::RBrace && self.current_token != Token::EOF {
+            // A file's imports and exports are part of its interface, so
+            // they cannot hide inside blocks or functions.
+            let statement = if matches!(self.current_token.token, Token::Import | Token::Export) {
+                let tok = self.current_token.clone();
+                let message = format!("'{}' is only allowed at the top level", tok.token);
+                self.error_at(&tok, &[], message);
+                None
+            } else {
+                self.parse_statement()
+            };
+
+            match statement {
+                Some(stmt) => {
+                    statements.push(stmt);
+                    self.advance_tokens();
+                }
+                None => {
+                    // Stops on a ';' to step over, or on what should be our '}'.
+                    self.synchronize();
+                    if self.current_token == Token::Semicolon {
+                        self.advance_tokens();
+                    }
+                }
+            }
+        }
+
+        if self.current_token == Token::EOF {
+            let tok = self.current_token.clone();
+            self.error_at(&tok, &["'}'"], "expected '}', found end of input".to_string());
+            return None;
+        }
+
+        Some(statements)
+    }
+
+    // current_token is TemplateStart; collects parts up to TemplateEnd.
+    fn parse_interpolated_string(&mut self) -> Option<Expression> {
+        let mut parts = vec![];
+
+        loop {
+            self.advance_tokens();
+            match self.current_token.token.clone() {
+                Token::StringFragment(text) => parts.push(InterpolationPart::Literal(text.into_owned())),
+                Token::InterpolationStart => {
+                    self.advance_tokens();
+                    let expr = self.parse_nested_expression()?;
+                    if !self.expect_peek(Token::InterpolationEnd) {
+                        return None;
+                    }
+                    parts.push(InterpolationPart::Expr(expr));
+                }
+                Token::TemplateEnd => return Some(Expression::Interpolated(parts)),
+                _ => return None, // the lexer only emits the tokens above here
+            }
+        }
     }
 
     fn parse_prefix_expression(&mut self) -> Option<Expression> {
-        let operator = match &self.current_token {
-            Token::Bang => "!",
-            Token::Minus => "-",
-            _ => return None,
-        }.to_string();
+        let operator = self.current_token.token.to_string();
+        let span = self.current_token.span;
 
         self.advance_tokens();
 
-        let right = self.parse_expression(Precedence::PREFIX)?;
+        // `**` binds tighter than a minus sign: -2 ** 2 == -(2 ** 2)
+        let precedence = match operator.as_str() {
+            "-" => Precedence::PRODUCT,
+            _ => Precedence::PREFIX,
+        };
+        let right = self.parse_expression(precedence)?;
 
         Some(Expression::Prefix {
             operator,
             right: Box::new(right),
+            span,
         })
     }
-    fn parse_infix_expression(&mut self, left: Expression) -> Expression {
-        let operator = match &self.current_token {
-            Token::Plus => "+",
-            Token::Minus => "-",
-            Token::Asterisk => "*",
-            Token::Slash => "/",
-            Token::LessThan => "<",
-            Token::GreaterThan => ">",
-            _ => "",
-        }.to_string();
+    fn parse_infix_expression(&mut self, left: Expression) -> Option<Expression> {
+        let operator = self.current_token.token.to_string();
+        let span = self.current_token.span;
 
-        let precedence = Self::get_precedence(&self.current_token);
+        // `**` is right associative: 2 ** 3 ** 2 == 2 ** (3 ** 2)
+        let precedence = match self.current_token.token {
+            Token::Power => Precedence::PRODUCT,
+            ref token => self.precedence_of(token),
+        };
 
         self.advance_tokens();
 
-        let right = self.parse_expression(precedence).unwrap();
+        let right = self.parse_expression(precedence)?;
 
-        Expression::Infix {
+        Some(Expression::Infix {
             left: Box::new(left),
             operator,
             right: Box::new(right),
-        }
+            span,
+        })
     }
-    fn expect_rparen(&mut self) {
-        if self.next_token == Token::RParen {
-            self.advance_tokens();
-        } else {
-            panic!("Expected ')' but found {:?}", self.next_token);
+
+    // x = 1, x += 1 and friends. The right side is parsed at LOWEST so that
+    // `a = b = 1` groups to the right.
+    fn parse_assign_expression(&mut self, target: Expression) -> Option<Expression> {
+        let operator = self.current_token.token.to_string();
+        let span = self.current_token.span;
+
+        if !is_assignable(&target) {
+            let tok = self.current_token.clone();
+            let message = format!("invalid assignment target for '{}'", operator);
+            self.error_at(&tok, &[], message);
+            return None;
         }
+
+        self.advance_tokens();
+
+        let value = self.parse_expression(Precedence::LOWEST)?;
+
+        Some(Expression::Assign {
+            target: Box::new(target),
+            operator,
+            value: Box::new(value),
+            span,
+        })
     }
 
     fn parse_expression_statement(&mut self) -> Option<Statement> {
-        let expr = self.parse_expression(Precedence::LOWEST)?;
+        // A statement that starts with a block-like expression ends at its
+        // closing '}', so `for x in xs { ... } [a, b]` is two statements.
+        let expr = if matches!(self.current_token.token, Token::If | Token::While | Token::Loop | Token::For | Token::Match | Token::Try) {
+            let prefix = self.prefix_parse_fns[&discriminant(&self.current_token.token)];
+            prefix(self)?
+        } else {
+            self.parse_expression(Precedence::LOWEST)?
+        };
 
         // If next token is a semicolon, consume it
         if self.next_token == Token::Semicolon {
@@ -196,4 +1474,31 @@ impl Parser {
         Some(Statement::ReturnStatement { value })
     }
 
+    fn parse_throw_statement(&mut self) -> Option<Statement> {
+        let span = self.current_token.span;
+        self.advance_tokens(); // move to the expression
+
+        let value = self.parse_expression(Precedence::LOWEST)?;
+
+        if self.next_token == Token::Semicolon {
+            self.advance_tokens();
+        }
+
+        Some(Statement::Throw { value, span })
+    }
+
+}
+
+// Expressions that name a place a value can be stored in.
+fn is_assignable(target: &Expression) -> bool {
+    matches!(target, Expression::Identifier { .. } | Expression::Index { .. } | Expression::Field { .. })
+}
+
+// How a token is named in "found ..." messages.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::EOF => "end of input".to_string(),
+        Token::Illegal => "illegal token".to_string(),
+        other => format!("'{}'", other),
+    }
 }
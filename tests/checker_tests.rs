@@ -0,0 +1,202 @@
+use aion::checker::{check_program, Checker, Severity};
+use aion::lexer::Lexer;
+use aion::parser::Parser;
+use aion::ast::Statement;
+
+fn parse(input: &str) -> Vec<Statement> {
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "parser errors: {:?}", parser.errors());
+    program
+}
+
+fn check(input: &str) -> Vec<String> {
+    check_program(&parse(input)).iter().map(|err| err.to_string()).collect()
+}
+
+#[test]
+fn test_well_typed_programs() {
+    let inputs = vec![
+        "let x: int = 5; x + 1;",
+        "let y: float = 1 + 2.5;",
+        "fn add(a: int, b: int) -> int { a + b } add(1, 2) * 3;",
+        "fn f(a: int) -> string { if a > 0 { return \"pos\"; } \"neg\" }",
+        "let xs: [int] = [1, 2]; xs[0] = 3; xs[0] + 1;",
+        "let h: {string: int} = {\"a\": 1}; h[\"a\"] * 2;",
+        // Let-bound functions are generic, and reassigned bindings fall back to any.
+        "let f = fn(a, b) { a + b }; f(1, 2); f(\"a\", \"b\");",
+        // Arithmetic with a number works on ints and floats alike.
+        "let inc = fn(x) { x + 1 }; inc(1); inc(2.5); let scale = fn(x) { 2.5 * x }; scale(inc(1));",
+        "let x = 5; x = \"five\"; x + \"!\";",
+        "let xs = [1]; xs[0] = \"a\"; xs[0] + \"b\";",
+        "struct Point { x, y } let p: Point = Point { x: 1, y: 2 }; p.x;",
+        "let o: Option = Some(1); let n: any = null;",
+        "let apply: fn(int) -> int = fn(x: int) -> int { x * 2 };",
+    ];
+
+    for input in inputs {
+        assert_eq!(check(input), Vec::<String>::new(), "input: {}", input);
+    }
+}
+
+#[test]
+fn test_type_errors() {
+    let cases = vec![
+        ("5 + true;", "cannot apply '+' to int and bool at 1:3", Severity::Warning),
+        ("let x: int = \"five\";", "expected int, found string at 1:8", Severity::Error),
+        ("let s = \"a\";\ns - 1;", "cannot apply '-' to string and int at 2:3", Severity::Warning),
+        ("-\"a\";", "cannot apply '-' to string at 1:1", Severity::Warning),
+        ("let x: int = 1; x = 2.5;", "expected int, found float at 1:19", Severity::Error),
+        ("fn add(a: int, b: int) -> int { a + b } add(1);", "expected 2 arguments but got 1 at 1:44", Severity::Warning),
+        ("fn add(a: int, b: int) -> int { a + b } add(1, true);", "argument 2: expected int, found bool at 1:44", Severity::Error),
+        ("fn f() -> int { \"no\" }", "function returns string, but its return type is int at 1:11", Severity::Error),
+        ("fn f(a) -> int { return [a]; }", "function returns ['a], but its return type is int at 1:12", Severity::Error),
+        ("let x = 1; x();", "cannot call int, it is not a function at 1:13", Severity::Warning),
+        ("[1, 2][\"a\"];", "index must be an integer, not string at 1:7", Severity::Warning),
+        ("5[0];", "cannot index int at 1:2", Severity::Warning),
+        ("let n = 5; n.x;", "cannot access field 'x' on int at 1:13", Severity::Warning),
+        ("for c in 5 { c; }", "cannot iterate over int at 1:1", Severity::Warning),
+        ("let p: Pointt = 1;", "unknown type 'Pointt' at 1:8", Severity::Error),
+        ("fn f(x: int) { x } f(f);", "argument 1: expected int, found fn(int) -> int at 1:21", Severity::Error),
+    ];
+
+    for (input, expected, severity) in cases {
+        assert_eq!(check(input), vec![expected.to_string()], "input: {}", input);
+        assert_eq!(check_program(&parse(input))[0].severity, severity, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_checker_keeps_globals_between_checks() {
+    let mut checker = Checker::new();
+    assert!(checker.check(&parse("let total: int = 0;")).is_empty());
+    assert!(checker.check(&parse("struct Point { x, y }")).is_empty());
+
+    let errors: Vec<String> = checker.check(&parse("total = \"x\";")).iter().map(|err| err.to_string()).collect();
+    assert_eq!(errors, vec!["expected int, found string at 1:7"]);
+    assert!(checker.check(&parse("let p: Point = Point { x: 1, y: 2 };")).is_empty());
+
+    // A rejected program does not run, so nothing it declared is kept.
+    let errors = checker.check(&parse("let total = \"reset\"; let bad: int = \"x\";"));
+    assert_eq!(errors[0].severity, Severity::Error);
+    assert_eq!(checker.type_of("total").as_deref(), Some("int"));
+    assert_eq!(checker.type_of("bad"), None);
+    // One with only warnings runs.
+    let errors = checker.check(&parse("let n = 5; n.x;"));
+    assert_eq!(errors[0].severity, Severity::Warning);
+    assert_eq!(checker.type_of("n").as_deref(), Some("int"));
+}
+
+#[test]
+fn test_inferred_types() {
+    let mut checker = Checker::new();
+    let input = "
+        let id = fn(x) { x };
+        let compose = fn(f, g) { fn(x) { f(g(x)) } };
+        fn shift(x) { x << 1 }
+        fn twice(f, x) { f(f(x)) }
+        fn greet(name) { \"hi \" + name }
+        let n = id(1);
+    ";
+    assert!(checker.check(&parse(input)).is_empty());
+
+    let cases = vec![
+        ("id", "fn('a) -> 'a"),
+        ("compose", "fn(fn('a) -> 'b, fn('c) -> 'a) -> fn('c) -> 'b"),
+        ("shift", "fn(int) -> int"),
+        ("twice", "fn(fn('a) -> 'a, 'a) -> 'a"),
+        ("greet", "fn(string) -> string"),
+        ("n", "int"),
+    ];
+    for (name, expected) in cases {
+        assert_eq!(checker.type_of(name).as_deref(), Some(expected), "name: {}", name);
+    }
+
+    // Each use of a let-bound function gets its own copy of its type.
+    assert!(checker.check(&parse("id(1) + id(2); id(\"a\") + \"b\"; compose(shift, id)(3);")).is_empty());
+
+    // So does any let-bound value that is a function: an alias, or what a
+    // partial application returns.
+    let input = "
+        let alias = id;
+        alias(1); alias(\"s\");
+        let constant = fn(a) { fn(b) { a } };
+        let one = constant(1);
+        one(true) + one(\"s\");
+        let twice_id = compose(id, id);
+        twice_id(1) + 1; twice_id(\"s\") + \"!\";
+    ";
+    assert_eq!(checker.check(&parse(input)), vec![]);
+    assert_eq!(checker.type_of("alias").as_deref(), Some("fn('a) -> 'a"));
+    assert_eq!(checker.type_of("one").as_deref(), Some("fn('a) -> int"));
+}
+
+#[test]
+fn test_unification_errors() {
+    let cases = vec![
+        ("let id = fn(x) { x };\nid(1) + id(true);", "cannot apply '+' to int (from 2:3) and bool (from 2:11) at 2:7"),
+        ("fn shift(x) { x << 1 }\nshift(true);", "argument 1: expected int (from 1:17), found bool at 2:6"),
+        // Parameters are not generalized, only let-bound functions.
+        ("fn both(f) { f(1); f(true) }", "argument 1: expected int (from 1:15), found bool at 1:21"),
+        ("fn apply(f) { f(f) }", "cannot call 'a as fn('a) -> 'b at 1:16"),
+        ("fn greet(name) { \"hi \" + name }\ngreet(5);", "argument 1: expected string (from 1:24), found int at 2:6"),
+        // Arithmetic with a number only takes numbers.
+        ("let f = fn(x) { x + 1 };\nf(\"s\");", "argument 1: expected int or float, found string at 2:2"),
+        ("let f = fn(x) { x + 1 };\nf(true);", "argument 1: expected int or float, found bool at 2:2"),
+        ("fn half(x) { x / 2.0 }\nhalf([1]);", "argument 1: expected int or float, found [int] at 2:5"),
+        ("fn f(x) { let y = x; y * 2; x + \"!\" }", "cannot apply '+' to int or float and string at 1:31"),
+        // A check the checker cannot follow can make these fine.
+        ("let f = fn(x) { if (x == null) { 0 } else { x + 1 } };\nf(null);", "argument 1: expected int or float, found null at 2:2"),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(check(input), vec![expected.to_string()], "input: {}", input);
+        // Nothing here goes against an annotation, so none keeps the program from running.
+        let severities: Vec<Severity> = check_program(&parse(input)).iter().map(|err| err.severity).collect();
+        assert_eq!(severities, vec![Severity::Warning], "input: {}", input);
+    }
+}
+
+const TRAITS: &str = "
+trait Show { fn show(self); fn loud(self) { self.show() + \"!\" } }
+struct Point { x, y }
+impl Show for Point { fn show(self) { \"p\" } }
+fn describe<T: Show>(value: T) { value.show() }
+fn largest<T: Ord>(a: T, b: T) -> T { if a > b { a } else { b } }
+";
+
+#[test]
+fn test_traits_and_bounded_generics() {
+    let inputs = vec![
+        "describe(Point { x: 1, y: 2 });",
+        "largest(1, 2) + 3;",
+        "largest(\"a\", \"b\") + \"c\";",
+        // A bound is checked once the arguments decide the type.
+        "let pick = fn(x) { largest(x, 2) }; pick(1);",
+        "fn same<T: Eq>(a: T, b: T) { a == b } same([1], [2]);",
+        "fn shout<T: Show>(value: T) { value.loud() }",
+        "struct V { n }\nimpl Ord for V { fn cmp(self, other) { self.n - other.n } }\nV { n: 1 } < V { n: 2 } && largest(V { n: 1 }, V { n: 2 }).n > 0;",
+    ];
+    for input in inputs {
+        assert_eq!(check(&format!("{}{}", TRAITS, input)), Vec::<String>::new(), "input: {}", input);
+    }
+
+    let cases = vec![
+        ("describe(5);", "int does not implement Show at 7:9"),
+        ("largest(Point { x: 1, y: 2 }, Point { x: 3, y: 4 });", "Point does not implement Ord at 7:8"),
+        ("largest(true, false);", "bool does not implement Ord at 7:8"),
+        ("largest(1, \"a\");", "argument 2: expected int, found string at 7:8"),
+        ("fn f<T: Show>(a: T) { a.hide() }", "T has no method 'hide' at 7:24"),
+        ("fn f<T>(a: T) { a.x }", "cannot access field 'x' on T at 7:18"),
+        ("fn f<T: Show>(a: T, b: T) { a < b }", "cannot apply '<' to T and T at 7:31"),
+        ("Point { x: 1, y: 2 } < Point { x: 3, y: 4 };", "cannot apply '<' to Point and Point at 7:22"),
+        ("fn f<T>(a: T) -> int { a }", "function returns T, but its return type is int at 7:18"),
+        ("fn f<T: Shw>(a: T) { a }", "unknown trait 'Shw' at 7:6"),
+        ("impl Shw for Point { }", "unknown trait 'Shw' at 7:1"),
+        ("impl Show for Point { }", "impl Show for Point is missing method 'show' at 7:1"),
+        ("impl Show for Point { fn show(self) { 1 } fn hide(self) { 2 } }", "method 'hide' is not a member of trait Show at 7:1"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(check(&format!("{}{}", TRAITS, input)), vec![expected.to_string()], "input: {}", input);
+    }
+}
@@ -1,6 +1,6 @@
 use aion::lexer::Lexer;
 use aion::parser::Parser;
-use aion::ast::{Statement, Expression};
+use aion::ast::Statement;
 
 #[test]
 fn test_infix_expression() {
@@ -12,7 +12,7 @@ fn test_infix_expression() {
     let program = parser.parse_program();
 
     match &program[0] {
-        Statement::LetStatement { name, value } => {
+        Statement::LetStatement { name, value, .. } => {
             assert_eq!(name, "x");
             println!("{:#?}", value);
         }
@@ -1,6 +1,11 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::path::Path;
+
+use aion::ast::Statement;
 use aion::lexer::Lexer;
 use aion::parser::Parser;
-use aion::interpreter::{eval_program, Environment, Object};
+use aion::interpreter::{eval_program, run_file, AssignError, Environment, ErrorKind, Object};
 
 #[test]
 fn test_let_and_ident() {
@@ -48,4 +53,1059 @@ fn test_arithmetic_and_comparison() {
     }
 }
 
-// Note: For the boolean case 5 < 10; we check Object::Boolean(true); the test uses expected_int==1 as a simple guard.
\ No newline at end of file
+// Note: For the boolean case 5 < 10; we check Object::Boolean(true); the test uses expected_int==1 as a simple guard.
+#[test]
+fn test_string_concatenation() {
+    let input = r#"
+        let greeting = "hello";
+        let name = "aion";
+        greeting + ", " + name;
+    "#;
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let mut env = Environment::new();
+    let result = eval_program(program, &mut env);
+
+    assert_eq!(result, Object::String("hello, aion".to_string()));
+}
+
+#[test]
+fn test_float_arithmetic() {
+    let cases = vec![
+        ("1.5 + 2.25;", Object::Float(3.75)),
+        ("0x10 * 0.5;", Object::Float(8.0)),
+        ("1 / 4.0;", Object::Float(0.25)),
+        ("-2.5;", Object::Float(-2.5)),
+        ("1_000 < 1e4;", Object::Boolean(true)),
+    ];
+
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        let mut env = Environment::new();
+        assert_eq!(eval_program(program, &mut env), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_extended_operators() {
+    let cases = vec![
+        ("7 % 3;", Object::Integer(1)),
+        ("2 ** 3 ** 2;", Object::Integer(512)),
+        ("2 * 3 ** 2;", Object::Integer(18)),
+        ("6 & 3 | 8;", Object::Integer(10)),
+        ("6 ^ 3;", Object::Integer(5)),
+        ("1 << 4 >> 2;", Object::Integer(4)),
+        ("1 + 1 << 2;", Object::Integer(8)),
+        ("3 <= 3;", Object::Boolean(true)),
+        ("2 >= 3;", Object::Boolean(false)),
+        ("1 < 2 && 3 > 4;", Object::Boolean(false)),
+        ("1 > 2 || 3 < 4;", Object::Boolean(true)),
+        ("2 ** -1;", Object::Float(0.5)),
+        ("-2 ** 2;", Object::Integer(-4)),
+        ("(-2) ** 2;", Object::Integer(4)),
+        ("-2 * 3 ** 2;", Object::Integer(-18)),
+        ("2 ** -1 ** 2;", Object::Float(0.5)),
+        ("7.5 % 2;", Object::Float(1.5)),
+    ];
+
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        let mut env = Environment::new();
+        assert_eq!(eval_program(program, &mut env), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_short_circuit_and_compound_assignment() {
+    let input = "
+        let x = 10;
+        x += 5;
+        x *= 2;
+        x -= 6;
+        x /= 3;
+        0 && (x += 100);
+        1 || (x += 100);
+        x;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let mut env = Environment::new();
+    assert_eq!(eval_program(program, &mut env), Object::Integer(8));
+}
+
+#[test]
+fn test_string_interpolation() {
+    let input = r#"
+        let a = 2;
+        let b = 3;
+        let name = "aion";
+        "${name}: ${a} + ${b} = ${a + b}, nested ${"<${a * b}>"}";
+    "#;
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let mut env = Environment::new();
+    assert_eq!(
+        eval_program(program, &mut env),
+        Object::String("aion: 2 + 3 = 5, nested <6>".to_string())
+    );
+}
+
+#[test]
+fn test_equality() {
+    let cases = vec![
+        ("5 == 5;", true),
+        ("5 != 5;", false),
+        ("1 < 2 == 3 < 4;", true),
+        (r#""a" == "a";"#, true),
+        (r#""a" != "b";"#, true),
+        ("1.5 == 1.5;", true),
+    ];
+
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        let mut env = Environment::new();
+        assert_eq!(eval_program(program, &mut env), Object::Boolean(expected), "input: {}", input);
+    }
+}
+
+#[test]
+fn test_boolean_and_null_literals() {
+    let cases = vec![
+        ("true;", Object::Boolean(true)),
+        ("!true;", Object::Boolean(false)),
+        ("let ok = false; ok;", Object::Boolean(false)),
+        ("true == !false;", Object::Boolean(true)),
+        ("true && false || true;", Object::Boolean(true)),
+        ("null;", Object::Null),
+        ("!null;", Object::Boolean(true)),
+        ("null == null;", Object::Boolean(true)),
+        ("null != 0;", Object::Boolean(true)),
+    ];
+
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        let mut env = Environment::new();
+        assert_eq!(eval_program(program, &mut env), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_if_else_expressions() {
+    let cases = vec![
+        ("if true { 10 }", Object::Integer(10)),
+        ("if false { 10 }", Object::Null),
+        ("if 1 < 2 { 10 } else { 20 }", Object::Integer(10)),
+        ("if 1 > 2 { 10 } else { 20 }", Object::Integer(20)),
+        ("let x = 5; if x > 10 { 1 } else if x > 3 { 2 } else { 3 }", Object::Integer(2)),
+        ("let y = if (0) { 1 } else { let z = 7; z * 2 }; y;", Object::Integer(14)),
+        ("{ let a = 1; a + 1 }", Object::Integer(2)),
+    ];
+
+    for (input, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "input: {}", input);
+        let mut env = Environment::new();
+        assert_eq!(eval_program(program, &mut env), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_return_unwinds_through_blocks() {
+    let input = "
+        if 10 > 1 {
+            if 10 > 1 {
+                let inner = if true { return 10; } else { 0 };
+                inner;
+            }
+            return 1;
+        }
+        99;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let mut env = Environment::new();
+    assert_eq!(eval_program(program, &mut env), Object::Integer(10));
+}
+
+fn run(input: &str) -> Object {
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "parse errors in {:?}: {:?}", input, parser.errors());
+    let mut env = Environment::new();
+    eval_program(program, &mut env)
+}
+
+#[test]
+fn test_functions_and_calls() {
+    let cases = vec![
+        ("let add = fn(a, b) { a + b }; add(2, 3);", Object::Integer(5)),
+        ("let id = fn(x) { x }; id(id)(7);", Object::Integer(7)),
+        ("fn(x) { x * 2 }(21);", Object::Integer(42)),
+        ("let f = fn() { return 1; 2 }; f() + 10;", Object::Integer(11)),
+        ("let early = fn(x) { if x > 0 { return \"pos\"; } \"neg\" }; early(-1);", Object::String("neg".to_string())),
+        ("let fact = fn(n) { if n < 2 { 1 } else { n * fact(n - 1) } }; fact(10);", Object::Integer(3628800)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_return_inside_function_only_ends_that_call() {
+    let input = "
+        let first = fn() { return 1; };
+        let total = first() + first();
+        total + 1;
+    ";
+
+    assert_eq!(run(input), Object::Integer(3));
+}
+
+#[test]
+fn test_closures_capture_their_environment() {
+    let input = "
+        let make_adder = fn(n) { fn(x) { x + n } };
+        let add_two = make_adder(2);
+        let add_ten = make_adder(10);
+        let inner = fn() { let n = 100; add_two(1) };
+        inner() + add_ten(1);
+    ";
+
+    assert_eq!(run(input), Object::Integer(14));
+}
+
+#[test]
+fn test_call_errors() {
+    match run("let x = 5; x(1);") {
+        Object::Error(err) => assert_eq!(err.to_string(), "cannot call integer, it is not a function at 1:13"),
+        other => panic!("expected error, found {:?}", other),
+    }
+    match run("let f = fn(a) { a }; f(1, 2);") {
+        Object::Error(err) => assert_eq!(err.message, "expected 1 arguments but got 2"),
+        other => panic!("expected error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_block_and_function_scopes() {
+    let cases = vec![
+        // `let` in a block shadows; the outer binding comes back afterwards.
+        ("let x = 1; { let x = 2; x; } x;", Object::Integer(1)),
+        ("let x = 1; if true { let x = 2; } x;", Object::Integer(1)),
+        ("let x = 1; { let x = 2; x }", Object::Integer(2)),
+        // Assignment reaches out to the nearest existing binding.
+        ("let x = 1; { x += 10; } x;", Object::Integer(11)),
+        ("let x = 1; let f = fn() { let y = 5; x += y; }; f(); x;", Object::Integer(6)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+
+    // Names bound inside a block or call do not leak out.
+    let undefined = vec![
+        ("{ let hidden = 1; } hidden;", "undefined variable 'hidden' at 1:21"),
+        ("let f = fn(p) { let q = p; q }; f(3); p;", "undefined variable 'p' at 1:39"),
+        ("let x = 1;\nx + y;", "undefined variable 'y' at 2:5"),
+    ];
+    for (input, expected) in undefined {
+        match run(input) {
+            Object::Error(err) => {
+                assert_eq!(err.kind, ErrorKind::Name, "input: {}", input);
+                assert_eq!(err.to_string(), expected, "input: {}", input);
+            }
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_closures_share_captured_state() {
+    let input = "
+        let counter = fn() {
+            let count = 0;
+            fn() { count += 1; count }
+        };
+        let a = counter();
+        let b = counter();
+        a(); a();
+        b();
+        a() * 10 + b();
+    ";
+
+    assert_eq!(run(input), Object::Integer(32));
+}
+
+#[test]
+fn test_environment_scope_chain() {
+    let global = Environment::new();
+    global.define("x".to_string(), Object::Integer(1));
+
+    let inner = global.enclosed();
+    assert_eq!(inner.get("x"), Some(Object::Integer(1)));
+
+    inner.define("x".to_string(), Object::Integer(2));
+    assert_eq!(inner.get("x"), Some(Object::Integer(2)));
+    assert_eq!(global.get("x"), Some(Object::Integer(1)));
+
+    assert_eq!(global.enclosed().assign("x", Object::Integer(3)), Ok(()));
+    assert_eq!(global.get("x"), Some(Object::Integer(3)));
+    assert_eq!(inner.assign("missing", Object::Null), Err(AssignError::Undeclared));
+    assert_eq!(global.get("missing"), None);
+}
+
+#[test]
+fn test_loops() {
+    let cases = vec![
+        ("let i = 0; let sum = 0; while i < 5 { i += 1; sum += i; } sum;", Object::Integer(15)),
+        ("let i = 0; while i < 10 { i += 1; if i == 3 { break; } } i;", Object::Integer(3)),
+        ("let n = 0; let odd = 0; while n < 6 { n += 1; if n % 2 == 0 { continue; } odd += 1; } odd;", Object::Integer(3)),
+        ("let n = 1; loop { n *= 2; if n > 100 { break n; } }", Object::Integer(128)),
+        ("while false { 1 }", Object::Null),
+        ("let out = \"\"; for c in \"abc\" { out += c + c; } out;", Object::String("aabbcc".to_string())),
+        ("let count = 0; for c in \"a-b-c\" { if c == \"-\" { continue; } count += 1; } count;", Object::Integer(3)),
+        // `break` leaves only the innermost loop.
+        ("let total = 0; for a in \"xy\" { for b in \"123\" { if b == \"2\" { break; } total += 1; } } total;", Object::Integer(2)),
+        // `return` passes straight through loops.
+        ("let find = fn(s) { for c in s { if c == \"!\" { return true; } } false }; find(\"hi!\");", Object::Boolean(true)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+
+    // The parser rejects a stray `break`; a program built without it is
+    // still a syntax error.
+    for statement in [Statement::Break { value: None }, Statement::Continue] {
+        match eval_program(vec![statement], &mut Environment::new()) {
+            Object::Error(err) => assert_eq!(err.kind, ErrorKind::Syntax),
+            other => panic!("expected a syntax error, found {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_for_over_a_non_iterable_is_an_error() {
+    match run("for x in 5 { x }") {
+        Object::Error(err) => assert_eq!(err.to_string(), "cannot iterate over integer at 1:1"),
+        other => panic!("expected error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_assignment_expressions() {
+    let cases = vec![
+        ("let x = 1; x = x + 1; x;", Object::Integer(2)),
+        ("let x = 1; x = 5;", Object::Integer(5)),
+        ("let a = 0; let b = 0; a = b = 7; a + b;", Object::Integer(14)),
+        ("let x = 1; { x = 2; } x;", Object::Integer(2)),
+        ("let x = 1; { let x = 0; x = 2; } x;", Object::Integer(1)),
+        ("const limit = 3; let i = 0; while i < limit { i = i + 1; } i;", Object::Integer(3)),
+        // An inner `let` may shadow a const.
+        ("const x = 1; { let x = 2; x = 3; x }", Object::Integer(3)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_assignment_errors_name_the_variable() {
+    let cases = vec![
+        ("y = 1;", "cannot assign to undeclared variable 'y' at 1:3"),
+        ("let f = fn() { z += 1 }; f();", "cannot assign to undeclared variable 'z' at 1:18"),
+        ("const x = 1;\nx = 2;", "cannot assign to const 'x' at 2:3"),
+        ("const x = 1; let f = fn() { x *= 2 }; f();", "cannot assign to const 'x' at 1:31"),
+        // Nor can a declaration in the const's own scope replace it.
+        ("const x = 1;\nlet x = 2;", "cannot redeclare const 'x' at 2:1"),
+        ("const x = 1; const x = 2;", "cannot redeclare const 'x' at 1:14"),
+        ("const x = 1;\nfn x() { 2 }", "cannot redeclare const 'x' at 2:1"),
+        ("const x = 1;\nlet [x, y] = [2, 3];", "cannot redeclare const 'x' at 2:1"),
+    ];
+
+    for (input, expected) in cases {
+        match run(input) {
+            Object::Error(err) => {
+                assert_eq!(err.kind, ErrorKind::Name, "input: {}", input);
+                assert_eq!(err.to_string(), expected, "input: {}", input);
+            }
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+fn array(values: Vec<Object>) -> Object {
+    Object::Array(Rc::new(RefCell::new(values)))
+}
+
+#[test]
+fn test_arrays_indexing_and_slicing() {
+    let ints = |values: &[i64]| array(values.iter().map(|&i| Object::Integer(i)).collect());
+
+    let cases = vec![
+        ("[1, 2 * 2, 3 + 3];", ints(&[1, 4, 6])),
+        ("[];", ints(&[])),
+        ("let a = [10, 20, 30]; a[0] + a[2];", Object::Integer(40)),
+        ("let a = [10, 20, 30]; a[-1];", Object::Integer(30)),
+        ("[[1, 2], [3, 4]][1][0];", Object::Integer(3)),
+        ("let a = [1, 2, 3, 4, 5]; a[1:3];", ints(&[2, 3])),
+        ("let a = [1, 2, 3, 4, 5]; a[:2];", ints(&[1, 2])),
+        ("let a = [1, 2, 3, 4, 5]; a[-2:];", ints(&[4, 5])),
+        ("let a = [1, 2, 3]; a[:];", ints(&[1, 2, 3])),
+        ("\"hello\"[1];", Object::String("e".to_string())),
+        ("\"hello\"[1:-1];", Object::String("ell".to_string())),
+        ("let get = fn() { [5, 6] }; get()[1];", Object::Integer(6)),
+        ("let sum = 0; for x in [1, 2, 3] { sum += x; } sum;", Object::Integer(6)),
+        ("[1, [2]] == [1, [2]];", Object::Boolean(true)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_index_assignment_mutates_shared_arrays() {
+    let cases = vec![
+        ("let a = [1, 2, 3]; a[1] = 20; a;", array(vec![Object::Integer(1), Object::Integer(20), Object::Integer(3)])),
+        ("let a = [1, 2]; let b = a; b[-1] *= 10; a[1];", Object::Integer(20)),
+        ("let a = [0]; let bump = fn(xs) { xs[0] += 1; }; bump(a); bump(a); a[0];", Object::Integer(2)),
+        ("let a = [1, 2, 3]; let b = a[:]; b[0] = 9; a[0];", Object::Integer(1)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_index_errors() {
+    let cases = vec![
+        ("[1, 2, 3][3];", "index 3 out of bounds for length 3 at 1:10"),
+        ("[1, 2, 3][-4];", "index -4 out of bounds for length 3 at 1:10"),
+        ("let a = [1];\na[5] = 0;", "index 5 out of bounds for length 1 at 2:2"),
+        ("[1, 2, 3][2:1];", "slice 2:1 out of bounds for length 3 at 1:10"),
+        ("[1, 2, 3][:9];", "slice :9 out of bounds for length 3 at 1:10"),
+        ("[1][\"0\"];", "index must be an integer, not string at 1:4"),
+        ("5[0];", "cannot index integer at 1:2"),
+        ("let s = \"abc\"; s[0] = \"x\";", "cannot assign into string at 1:17"),
+    ];
+
+    for (input, expected) in cases {
+        match run(input) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_hash_literals_and_lookup() {
+    let cases = vec![
+        ("let h = {\"name\": \"aion\", 1: true, false: 0}; h[\"name\"];", Object::String("aion".to_string())),
+        ("let h = {\"name\": \"aion\", 1: true, false: 0}; h[1];", Object::Boolean(true)),
+        ("let h = {\"name\": \"aion\", 1: true, false: 0}; h[false];", Object::Integer(0)),
+        ("let k = \"a\"; ({k + \"b\": 1 + 1})[\"ab\"];", Object::Integer(2)),
+        ("let h = {}; h[\"x\"] = 1; h[\"x\"] += 1; h[\"x\"];", Object::Integer(2)),
+        ("let h = {\"a\": 1}; let alias = h; alias[\"b\"] = 2; h[\"b\"];", Object::Integer(2)),
+        ("({\"a\": 1, \"b\": 2}) == {\"b\": 2, \"a\": 1};", Object::Boolean(true)),
+        ("let cfg = {\"port\": 80, \"host\": \"local\",}; cfg[\"port\"];", Object::Integer(80)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_hashes_keep_insertion_order() {
+    let input = "
+        let h = {\"z\": 1, \"a\": 2};
+        h[\"m\"] = 3;
+        h[\"z\"] = 10;
+        let order = \"\";
+        for k in h { order += k; }
+        [order, keys(h), values(h)];
+    ";
+
+    let strings = |values: &[&str]| array(values.iter().map(|s| Object::String(s.to_string())).collect());
+    assert_eq!(
+        run(input),
+        array(vec![
+            Object::String("zam".to_string()),
+            strings(&["z", "a", "m"]),
+            array(vec![Object::Integer(10), Object::Integer(2), Object::Integer(3)]),
+        ])
+    );
+    assert_eq!(run("let h = {\"a\": [1], 2: \"b\"}; h;").to_string(), "{\"a\": [1], 2: \"b\"}");
+}
+
+#[test]
+fn test_hash_key_errors() {
+    let cases = vec![
+        ("let h = {[1]: 2};", "cannot use array as a hash key at 1:10"),
+        ("let h = {};\nh[fn() { 1 }] = 2;", "cannot use function as a hash key at 2:2"),
+        ("let h = {1.5: 2};", "cannot use float as a hash key at 1:10"),
+        ("let h = {\n  \"a\": 1,\n  [2]: 3\n};", "cannot use array as a hash key at 3:3"),
+        ("let h = {\"a\": 1}; h[\"b\"];", "key \"b\" not found in hash at 1:20"),
+        ("keys([1]);", "keys() expects a hash, not array at 1:5"),
+    ];
+
+    for (input, expected) in cases {
+        match run(input) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_match_expressions() {
+    let describe = "
+        let describe = fn(v) {
+            match v {
+                0 => \"zero\",
+                -1 => \"minus one\",
+                n if n > 100 => \"big\",
+                _ => \"other\",
+            }
+        };
+    ";
+
+    let cases = vec![
+        (format!("{} describe(0);", describe), Object::String("zero".to_string())),
+        (format!("{} describe(-1);", describe), Object::String("minus one".to_string())),
+        (format!("{} describe(500);", describe), Object::String("big".to_string())),
+        (format!("{} describe(7);", describe), Object::String("other".to_string())),
+        ("match [1, 2, 3] { [first, ..rest] => first + rest[1] }".to_string(), Object::Integer(4)),
+        ("match [1, 2, 3] { [.., last] => last }".to_string(), Object::Integer(3)),
+        ("match [1, 2, 3] { [a, b] => 0, [a, _, c] => a + c }".to_string(), Object::Integer(4)),
+        ("match [] { [x, ..] => x, [] => \"empty\" }".to_string(), Object::String("empty".to_string())),
+        ("match {\"k\": 5, \"other\": 1} { {\"k\": v} => v * 2, _ => 0 }".to_string(), Object::Integer(10)),
+        ("match {\"k\": [1, 2]} { {\"k\": [_, y]} => y, _ => 0 }".to_string(), Object::Integer(2)),
+        ("match \"text\" { {\"k\": v} => v, [x] => x, s => s + \"!\" }".to_string(), Object::String("text!".to_string())),
+        ("match 2.0 { 2 => \"two\", _ => \"?\" }".to_string(), Object::String("two".to_string())),
+        ("let x = 1; let y = match 5 { x => x }; y + x;".to_string(), Object::Integer(6)),
+        ("match 3 { n => { let d = n * 2; d + 1 } }".to_string(), Object::Integer(7)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(&input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_let_destructuring() {
+    let cases = vec![
+        ("let [a, b] = [1, 2]; a * 10 + b;", Object::Integer(12)),
+        ("let [head, ..tail] = [1, 2, 3]; tail;", array(vec![Object::Integer(2), Object::Integer(3)])),
+        ("let {\"x\": x, \"y\": [_, y]} = {\"x\": 1, \"y\": [2, 3]}; x + y;", Object::Integer(4)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_unmatched_patterns_are_errors() {
+    let cases = vec![
+        ("match 5 { 1 => 1, n if n < 0 => 2 }", "no match arm matches 5 at 1:1"),
+        ("let a = 0;\nlet [x, y] = [1];", "[1] does not match the pattern in this let at 2:1"),
+    ];
+
+    for (input, expected) in cases {
+        match run(input) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+const POINT: &str = "
+    struct Point { x, y }
+    impl Point {
+        fn origin() { Point { x: 0, y: 0 } }
+        fn norm2(self) { self.x * self.x + self.y * self.y }
+        fn shift(self, dx) { self.x += dx; self }
+    }
+";
+
+#[test]
+fn test_structs_fields_and_methods() {
+    let cases = vec![
+        ("let p = Point { x: 3, y: 4 }; p.x + p.y;", Object::Integer(7)),
+        ("let x = 1; let y = 2; let p = Point { y, x }; p.y;", Object::Integer(2)),
+        ("Point { x: 3, y: 4 }.norm2();", Object::Integer(25)),
+        ("let p = Point.origin(); p.shift(2).shift(3).x;", Object::Integer(5)),
+        ("let p = Point { x: 1, y: 1 }; let q = p; q.y = 10; q.y *= 2; p.y;", Object::Integer(20)),
+        ("let ps = [Point { x: 1, y: 2 }]; ps[0].y;", Object::Integer(2)),
+        ("Point { x: 1, y: 2 } == Point { x: 1, y: 2 };", Object::Boolean(true)),
+        ("struct Other { x, y } Point { x: 1, y: 2 } == Other { x: 1, y: 2 };", Object::Boolean(false)),
+        // No struct literal in a condition: `p {` starts the body.
+        ("let p = Point { x: 1, y: 0 }; if p { p.x } else { 0 }", Object::Integer(1)),
+        ("let p = Point { x: 1, y: 0 }; if (Point { x: 1, y: 0 }) == p { \"same\" }", Object::String("same".to_string())),
+    ];
+
+    for (input, expected) in cases {
+        let input = format!("{} {}", POINT, input);
+        assert_eq!(run(&input), expected, "input: {}", input);
+    }
+
+    let shown = run(&format!("{} let p = Point {{ x: 1, y: \"a\" }}; p;", POINT));
+    assert_eq!(shown.to_string(), "Point { x: 1, y: \"a\" }");
+}
+
+#[test]
+fn test_struct_errors() {
+    let cases = vec![
+        ("Point { x: 1 };", "missing field 'y' in Point at 2:1"),
+        ("Point { x: 1, y: 2, z: 3 };", "Point has no field 'z' at 2:1"),
+        ("Point { x: 1, x: 2, y: 3 };", "field 'x' is given twice at 2:1"),
+        ("let p = Point { x: 1, y: 2 };\np.z;", "Point has no field 'z' at 3:2"),
+        ("let p = Point { x: 1, y: 2 };\np.z = 1;", "Point has no field 'z' at 3:2"),
+        ("let p = Point { x: 1, y: 2 };\np.missing();", "Point has no field 'missing' at 3:2"),
+        ("let n = 5; n.x;", "cannot access field 'x' on integer at 2:13"),
+        ("let n = 5; n { x: 1 };", "cannot construct n, it is not a struct at 2:12"),
+        ("impl Nope { fn f() { 1 } }", "cannot impl Nope, it is not a struct or enum at 2:1"),
+    ];
+
+    for (input, expected) in cases {
+        match run(&format!("struct Point {{ x, y }}\n{}", input)) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+const SHAPE: &str = "
+    enum Shape { Circle(r), Rect(w, h), Empty }
+    impl Shape {
+        fn area(self) {
+            match self {
+                Shape::Circle(r) => 3 * r * r,
+                Shape::Rect(w, h) => w * h,
+                Shape::Empty => 0,
+            }
+        }
+    }
+";
+
+#[test]
+fn test_enums_and_variant_patterns() {
+    let cases = vec![
+        ("Shape::Rect(2, 5).area();", Object::Integer(10)),
+        ("Shape::Circle(2).area() + Shape::Empty.area();", Object::Integer(12)),
+        ("Shape::Circle(1) == Shape::Circle(1);", Object::Boolean(true)),
+        ("Shape::Circle(1) == Shape::Rect(1, 1);", Object::Boolean(false)),
+        ("Shape::Empty == Shape::Empty;", Object::Boolean(true)),
+        ("let make = Shape::Circle; make(4).area();", Object::Integer(48)),
+        ("let Shape::Rect(w, _) = Shape::Rect(7, 8); w;", Object::Integer(7)),
+        ("match [Shape::Empty, 1] { [Shape::Circle(_), _] => 1, [Shape::Empty, n] => n + 1 }", Object::Integer(2)),
+        ("enum Other { Empty } Other::Empty == Shape::Empty;", Object::Boolean(false)),
+    ];
+
+    for (input, expected) in cases {
+        let input = format!("{} {}", SHAPE, input);
+        assert_eq!(run(&input), expected, "input: {}", input);
+    }
+
+    let shown = [
+        ("Shape::Circle(3);", "Shape::Circle(3)"),
+        ("Shape::Rect(1, \"a\");", "Shape::Rect(1, \"a\")"),
+        ("Shape::Empty;", "Shape::Empty"),
+        ("Shape;", "enum Shape"),
+        ("Shape::Rect;", "fn Shape::Rect(w, h)"),
+    ];
+    for (input, expected) in shown {
+        assert_eq!(run(&format!("{} {}", SHAPE, input)).to_string(), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_option_and_result_prelude() {
+    let cases = vec![
+        ("Some(5).unwrap_or(0);", Object::Integer(5)),
+        ("None.unwrap_or(0);", Object::Integer(0)),
+        ("Option::Some(1).is_some() && Option::None.is_none();", Object::Boolean(true)),
+        ("match None { Some(x) => x, None => \"nothing\" }", Object::String("nothing".to_string())),
+        ("let find = fn(xs, x) { for y in xs { if y == x { return Some(y); } } None }; find([1, 2], 3).unwrap_or(0);", Object::Integer(0)),
+        ("Err(\"bad\").is_err();", Object::Boolean(true)),
+        ("match Ok(3) { Ok(n) => n * 2, Err(_) => 0 }", Object::Integer(6)),
+        // The prelude sits outside the globals, so it can be shadowed.
+        ("let None = 1; None;", Object::Integer(1)),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_enum_errors() {
+    let cases = vec![
+        ("Shape::Square;", "Shape has no variant 'Square' at 2:6"),
+        ("Shape::Circle(1, 2);", "expected 1 arguments but got 2 at 2:14"),
+        ("let n = 5; n::x;", "cannot use '::' on integer at 2:13"),
+        ("match Shape::Empty { Shape::Rect(w) => w }", "Shape::Rect has 2 fields, but the pattern has 1 at 2:22"),
+        ("match 1 { Nope(x) => x }", "Nope is not an enum variant at 2:11"),
+        ("match Shape::Empty { Shape::Circle(r) => r }", "no match arm matches Shape::Empty at 2:1"),
+    ];
+
+    for (input, expected) in cases {
+        match run(&format!("enum Shape {{ Circle(r), Rect(w, h), Empty }}\n{}", input)) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_imports_and_exports() {
+    // Environment::new() resolves imports from the working directory, which
+    // for tests is the crate root.
+    let cases = vec![
+        ("import \"tests/modules/lib/math.aion\" as math; math.square(4) + math.pi;", Object::Integer(19)),
+        ("import \"tests/modules/lib/math.aion\" as math; let Pair = math.Pair; Pair { left: 1, right: 2 }.right;", Object::Integer(2)),
+        // Each file is evaluated once, so both names see the same module and types.
+        (
+            "import \"tests/modules/lib/math.aion\" as a; import \"tests/modules/lib/../lib/math.aion\" as b; a == b && a::Shape::Circle(1) == b::Shape::Circle(1);",
+            Object::Boolean(true),
+        ),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+
+    let shown = run("import \"tests/modules/lib/math.aion\" as math; math;");
+    assert_eq!(shown.to_string(), "module \"tests/modules/lib/math.aion\"");
+
+    assert_eq!(run_file(Path::new("tests/modules/main.aion")), Object::Integer(21));
+    // What the checker only warns about still runs.
+    assert_eq!(run_file(Path::new("tests/modules/nullable.aion")), Object::Integer(0));
+}
+
+#[test]
+fn test_import_errors() {
+    let cases = vec![
+        ("import \"tests/modules/lib/math.aion\" as math;\nmath.hidden;", "module \"tests/modules/lib/math.aion\" has no export 'hidden' at 2:5"),
+        ("import \"tests/modules/missing.aion\" as m;", "cannot import \"tests/modules/missing.aion\": No such file or directory (os error 2) at 1:1"),
+        ("import \"tests/modules/cycle_a.aion\" as a;", "import cycle: tests/modules/cycle_a.aion -> cycle_b.aion -> cycle_a.aion at 1:1"),
+        // The location is inside the imported file, which is type checked on its own.
+        ("import \"tests/modules/broken.aion\" as b;", "expected string, found int at 2:8"),
+        // Every syntax error in the file is reported in order, each at its location.
+        (
+            "import \"tests/modules/syntax.aion\" as s;",
+            "expected expression, found ';' at 1:9\nunexpected character '@' at 2:11",
+        ),
+    ];
+
+    for (input, expected) in cases {
+        match run(input) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+
+    match run("import \"tests/modules/syntax.aion\" as s;") {
+        Object::Error(err) => {
+            assert_eq!(err.kind, ErrorKind::Syntax);
+            assert_eq!(err.span.map(|span| span.to_string()), Some("1:9".to_string()));
+        }
+        other => panic!("expected a syntax error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_try_catch_finally() {
+    let cases = vec![
+        ("try { 1 } catch e { 2 }", Object::Integer(1)),
+        ("try { throw \"bad\"; 1 } catch e { e.message }", Object::String("bad".to_string())),
+        ("try { throw \"bad\"; } catch e { e.kind }", Object::String("Error".to_string())),
+        ("try { throw error(\"ParseError\", \"eof\"); } catch e { e.kind + \": \" + e.message }", Object::String("ParseError: eof".to_string())),
+        ("try { 1 / 0 } catch e { e.kind }", Object::String("ArithmeticError".to_string())),
+        ("try { 7 % 0 } catch e { e.message }", Object::String("modulo by zero".to_string())),
+        ("try { 1 + \"a\" } catch e { e.kind }", Object::String("TypeError".to_string())),
+        ("try { [1][5] } catch e { e.kind }", Object::String("IndexError".to_string())),
+        ("let h = {\"a\": 1}; try { h[\"b\"] } catch { \"missing\" }", Object::String("missing".to_string())),
+        // finally always runs, but its value is dropped.
+        ("let n = 0; try { n += 1; } finally { n += 10; 99 }; n;", Object::Integer(11)),
+        ("let n = 0; try { throw \"x\"; } catch { n += 1; } finally { n += 10; }; n;", Object::Integer(11)),
+        ("let f = fn() { try { return 1; } finally { 2 } }; f();", Object::Integer(1)),
+        ("let f = fn() { try { return 1; } finally { return 2; } }; f();", Object::Integer(2)),
+        ("let n = 0; for i in [1, 2, 3] { try { if i == 2 { break; } } finally { n += i; } } n;", Object::Integer(3)),
+        // Rethrowing keeps the original error.
+        ("try { try { 1 / 0 } catch e { throw e; } } catch e { e.kind }", Object::String("ArithmeticError".to_string())),
+        // A caught error is an ordinary value.
+        ("let e = try { throw \"x\"; } catch e { e }; e.message;", Object::String("x".to_string())),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_error_stacks() {
+    let input = "
+        let inner = fn(x) { x / 0 };
+        let outer = fn(x) { inner(x) + 1 };
+        try { outer(1) } catch e { e.stack }
+    ";
+    assert_eq!(
+        run(input),
+        array(vec![Object::String("inner at 3:34".to_string()), Object::String("outer at 4:20".to_string())])
+    );
+
+    match run("let f = fn() { throw \"boom\"; };\nf();") {
+        Object::Error(err) => {
+            assert_eq!(err.to_string(), "boom at 1:16");
+            assert_eq!(err.stack.len(), 1);
+            assert_eq!(err.stack[0].to_string(), "f at 2:2");
+        }
+        other => panic!("expected error, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_operator_failures_raise_errors() {
+    let cases = vec![
+        ("1 / 0;", ErrorKind::Arithmetic, "division by zero at 1:3"),
+        ("9223372036854775807 + 1;", ErrorKind::Arithmetic, "integer overflow at 1:21"),
+        ("2 ** 64;", ErrorKind::Arithmetic, "integer overflow at 1:3"),
+        ("\"a\" - \"b\";", ErrorKind::Type, "cannot apply '-' to string and string at 1:5"),
+        ("1 < \"a\";", ErrorKind::Type, "cannot apply '<' to integer and string at 1:3"),
+        ("-true;", ErrorKind::Type, "cannot apply '-' to boolean at 1:1"),
+        ("let x = 1;\nx /= 0;", ErrorKind::Arithmetic, "division by zero at 2:3"),
+        ("throw 5;", ErrorKind::Type, "cannot throw integer, only a string or an error at 1:1"),
+    ];
+
+    for (input, kind, expected) in cases {
+        match run(input) {
+            Object::Error(err) => {
+                assert_eq!(err.kind, kind, "input: {}", input);
+                assert_eq!(err.to_string(), expected, "input: {}", input);
+            }
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+
+    // Mismatched types can still be compared for equality.
+    assert_eq!(run("1 == \"1\";"), Object::Boolean(false));
+}
+
+const TRAITS: &str = "
+    trait Show {
+        fn show(self);
+        fn shout(self) { self.show() + \"!\" }
+    }
+    struct Point { x, y }
+    impl Show for Point {
+        fn show(self) { \"(${self.x}, ${self.y})\" }
+    }
+    enum Coin { Heads, Tails }
+    impl Show for Coin {
+        fn show(self) { match self { Coin::Heads => \"heads\", Coin::Tails => \"tails\" } }
+    }
+    fn describe<T: Show>(value: T) { value.show() }
+";
+
+#[test]
+fn test_traits_and_bounded_generics() {
+    let cases = vec![
+        ("Point { x: 1, y: 2 }.show();", Object::String("(1, 2)".to_string())),
+        // Default bodies fill in what an impl leaves out.
+        ("Point { x: 1, y: 2 }.shout();", Object::String("(1, 2)!".to_string())),
+        ("describe(Coin::Tails);", Object::String("tails".to_string())),
+        // Each value's own impl is called.
+        ("let out = \"\"; for v in [Point { x: 0, y: 0 }, Coin::Heads] { out += describe(v); } out;", Object::String("(0, 0)heads".to_string())),
+    ];
+
+    for (input, expected) in cases {
+        let input = format!("{} {}", TRAITS, input);
+        assert_eq!(run(&input), expected, "input: {}", input);
+    }
+    assert_eq!(run(&format!("{} Show;", TRAITS)).to_string(), "trait Show");
+}
+
+#[test]
+fn test_builtin_traits_on_user_types() {
+    let version = "
+        struct Version { major, minor }
+        impl Eq for Version {
+            fn eq(self, other) { self.major == other.major }
+        }
+        impl Ord for Version {
+            fn cmp(self, other) { (self.major - other.major) * 100 + self.minor - other.minor }
+        }
+        impl Hash for Version {
+            fn hash(self) { self.major }
+        }
+        impl Display for Version {
+            fn to_string(self) { \"v${self.major}.${self.minor}\" }
+        }
+        let a = Version { major: 1, minor: 2 };
+        let b = Version { major: 1, minor: 5 };
+    ";
+    let cases = vec![
+        ("a == b;", Object::Boolean(true)),
+        ("a != Version { major: 2, minor: 2 };", Object::Boolean(true)),
+        ("a < b;", Object::Boolean(true)),
+        ("b <= a;", Object::Boolean(false)),
+        ("\"${a}\";", Object::String("v1.2".to_string())),
+        ("print(a, 1);", Object::Null),
+        // Keys that hash the same and are equal are the same key.
+        ("let h = {a: \"first\"}; h[b] = \"second\"; h[a];", Object::String("second".to_string())),
+        ("let h = {a: 1}; h[b];", Object::Integer(1)),
+        ("let h = {a: 1}; \"${keys(h)[0]}\";", Object::String("v1.2".to_string())),
+        // Keys whose hashes collide stay apart unless they are equal.
+        (
+            "struct P { x } impl Hash for P { fn hash(self) { 1 } }\nlet h = {P { x: 1 }: \"a\", P { x: 2 }: \"b\"}; \"${keys(h)} ${h[P { x: 1 }]} ${h[P { x: 2 }]}\";",
+            Object::String("[P { x: 1 }, P { x: 2 }] a b".to_string()),
+        ),
+        // Without Eq, instances compare field by field.
+        ("struct P { x } P { x: 1 } == P { x: 1 };", Object::Boolean(true)),
+        // Values inside containers are compared through Eq too.
+        ("[a] == [b];", Object::Boolean(true)),
+        ("let h = {\"v\": a}; h == {\"v\": b};", Object::Boolean(true)),
+        ("struct Pair { x, y } Pair { x: a, y: 1 } == Pair { x: b, y: 1 };", Object::Boolean(true)),
+        ("Some(a) == Some(b);", Object::Boolean(true)),
+        ("[a] != [Version { major: 2, minor: 2 }];", Object::Boolean(true)),
+    ];
+
+    for (input, expected) in cases {
+        let input = format!("{} {}", version, input);
+        assert_eq!(run(&input), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_trait_errors() {
+    let cases = vec![
+        ("impl Show for Point { }", "impl Show for Point is missing method 'show' at 3:1"),
+        ("impl Show for Point { fn show(self) { 1 } fn hide(self) { 2 } }", "method 'hide' is not a member of trait Show at 3:1"),
+        ("impl Show for Point { fn show() { 1 } }", "method 'show' takes 1 parameters in trait Show but 0 here at 3:1"),
+        ("impl Point for Point { }", "cannot impl Point, it is not a trait at 3:1"),
+        (
+            "impl Show for Point { fn show(self) { 1 } }\nimpl Show for Point { fn show(self) { 2 } }",
+            "Point already implements Show at 4:1",
+        ),
+        ("fn f<T: Show>(x: T) { x }\nf(Point { x: 1, y: 2 });", "argument 1: Point does not implement Show at 4:2"),
+        ("fn f<T: Show>(x: T) { x }\nf(5);", "argument 1: integer does not implement Show at 4:2"),
+        ("fn f<T: Point>(x: T) { x }", "'Point' in the bounds of T is not a trait at 3:6"),
+        ("fn f<T: Ord>(x: T) { x }\nf(true);", "argument 1: boolean does not implement Ord at 4:2"),
+    ];
+
+    for (input, expected) in cases {
+        match run(&format!("trait Show {{ fn show(self); }}\nstruct Point {{ x, y }}\n{}", input)) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+
+    let hooks = vec![
+        ("impl Ord for P { fn cmp(self, other) { \"less\" } }\nP { x: 1 } < P { x: 2 };", "cmp must return an integer, not string at 4:12"),
+        ("impl Display for P { fn to_string(self) { 5 } }\n\"${P { x: 1 }}\";", "to_string must return a string, not integer"),
+    ];
+    for (input, expected) in hooks {
+        match run(&format!("struct P {{ x }}\n\n{}", input)) {
+            Object::Error(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+            other => panic!("expected error for {:?}, found {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_deep_recursion() {
+    // Debug builds use far more stack per call than release ones, so run
+    // on a thread whose stack is large enough for either.
+    let child = std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| {
+            let input = "let f = fn(n) { if n == 0 { 0 } else { 1 + f(n - 1) } }; f(1000);";
+            run(input) == Object::Integer(1000)
+        })
+        .unwrap();
+
+    assert!(child.join().unwrap());
+}
+
+#[test]
+fn test_recursion_limit() {
+    let child = std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(|| {
+            let input = "
+                let f = fn(n) { if n == 0 { 0 } else { 1 + f(n - 1) } };
+                let kind = try { f(100000) } catch e { e.kind };
+                [kind, f(10)];
+            ";
+            run(input).to_string()
+        })
+        .unwrap();
+
+    assert_eq!(child.join().unwrap(), "[\"RecursionError\", 10]");
+
+    // Each recursive call is the same frame, so the trace shows it once.
+    let child = std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(|| match run("let f = fn(n) { f(n + 1) };\nf(0);") {
+            Object::Error(err) => err.trace(),
+            other => panic!("expected a recursion error, found {:?}", other),
+        })
+        .unwrap();
+
+    assert_eq!(child.join().unwrap(), vec!["f at 1:18 (repeated 1999 times)", "f at 2:2"]);
+}
+
+#[test]
+fn test_self_referential_arrays() {
+    let cases = vec![
+        ("let a = [1]; a[0] = a; a;", "[[...]]"),
+        ("let a = [1, \"x\", 0]; a[2] = a; a;", "[1, \"x\", [...]]"),
+        ("let a = [1]; a[0] = a; a == a;", "true"),
+        ("let a = [1]; a[0] = a; let b = [1]; b[0] = b; a == b;", "true"),
+        ("let a = [1]; a[0] = a; let b = [2]; b[0] = [b]; a == b;", "true"),
+        ("let a = [1, 2]; a[0] = a; let b = [1, 3]; b[0] = b; a == b;", "false"),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input).to_string(), expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_self_referential_instances_and_hashes() {
+    let cases = vec![
+        ("struct N { next }\nlet n = N { next: null }; n.next = n; n;", "N { next: N {...} }"),
+        ("struct N { next }\nlet n = N { next: null }; n.next = n; n == n;", "true"),
+        (
+            "struct N { next }\nlet a = N { next: null }; a.next = N { next: a };\nlet b = N { next: null }; b.next = b; a == b;",
+            "true",
+        ),
+        ("let h = {\"a\": 1}; h[\"self\"] = h; h;", "{\"a\": 1, \"self\": {...}}"),
+        ("let h = {\"a\": 1}; h[\"self\"] = h; h == h;", "true"),
+        ("let h = {\"a\": [1]}; h[\"a\"][0] = h; h;", "{\"a\": [{...}]}"),
+        ("let h = {\"a\": 1}; h[\"self\"] = h; let g = {\"a\": 2}; g[\"self\"] = g; h == g;", "false"),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(run(input).to_string(), expected, "input: {}", input);
+    }
+}
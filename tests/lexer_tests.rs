@@ -1,4 +1,4 @@
-use aion::lexer::{Lexer, Token};
+use aion::lexer::{Lexer, Span, Token};
 
 #[test]
 fn test_next_token() {
@@ -7,8 +7,284 @@ fn test_next_token() {
     let mut lexer = Lexer::new(input);
 
     assert_eq!(lexer.next_token(), Token::Let);
-    assert_eq!(lexer.next_token(), Token::Ident("x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Ident("x".into()));
     assert_eq!(lexer.next_token(), Token::Assign);
-    assert_eq!(lexer.next_token(), Token::Number("10".to_string()));
+    assert_eq!(lexer.next_token(), Token::Int(10));
     assert_eq!(lexer.next_token(), Token::Semicolon);
 }
+
+#[test]
+fn test_token_spans() {
+    let input = "let x = 10;\n  y != 5;";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected = vec![
+        (Token::Let, 0, 3, 1, 1),
+        (Token::Ident("x".into()), 4, 5, 1, 5),
+        (Token::Assign, 6, 7, 1, 7),
+        (Token::Int(10), 8, 10, 1, 9),
+        (Token::Semicolon, 10, 11, 1, 11),
+        (Token::Ident("y".into()), 14, 15, 2, 3),
+        (Token::NotEqual, 16, 18, 2, 5),
+        (Token::Int(5), 19, 20, 2, 8),
+        (Token::Semicolon, 20, 21, 2, 9),
+        (Token::EOF, 21, 21, 2, 10),
+    ];
+
+    for (token, start, end, line, column) in expected {
+        let tok = lexer.next_token();
+        assert_eq!(tok, token);
+        assert_eq!(tok.span, Span { start, end, line, column });
+    }
+}
+
+#[test]
+fn test_spans_count_bytes_not_chars() {
+    let mut lexer = Lexer::new("é @ x");
+
+    let ident = lexer.next_token();
+    assert_eq!(ident, Token::Ident("é".into()));
+    assert_eq!(ident.span, Span { start: 0, end: 2, line: 1, column: 1 });
+
+    let illegal = lexer.next_token();
+    assert_eq!(illegal, Token::Illegal);
+    assert_eq!(illegal.span.column, 3);
+    assert_eq!(illegal.span.start, 3);
+}
+
+#[test]
+fn test_string_literals_and_escapes() {
+    let input = r#""hello" "a\tb\n" "say \"hi\"" "back\\slash" "\u{48}\u{1F600}""#;
+
+    let mut lexer = Lexer::new(input);
+
+    assert_eq!(lexer.next_token(), Token::String("hello".into()));
+    assert_eq!(lexer.next_token(), Token::String("a\tb\n".into()));
+    assert_eq!(lexer.next_token(), Token::String("say \"hi\"".into()));
+    assert_eq!(lexer.next_token(), Token::String("back\\slash".into()));
+    assert_eq!(lexer.next_token(), Token::String("H😀".into()));
+    assert_eq!(lexer.next_token(), Token::EOF);
+    assert!(lexer.errors().is_empty());
+}
+
+#[test]
+fn test_string_errors_have_positions() {
+    let mut lexer = Lexer::new("let s = \"bad \\q escape\";\nlet t = \"open");
+
+    while lexer.next_token() != Token::EOF {}
+
+    let errors = lexer.errors();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].message, "unknown escape sequence '\\q'");
+    assert_eq!(errors[0].span, Span { start: 13, end: 15, line: 1, column: 14 });
+    assert_eq!(errors[1].message, "unterminated string literal");
+    assert_eq!(errors[1].span, Span { start: 33, end: 38, line: 2, column: 9 });
+}
+
+#[test]
+fn test_comments_are_skipped() {
+    let input = "
+        // a line comment
+        let x = 10; // trailing
+        /* block /* nested */ still comment */
+        x / 2;
+    ";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected = vec![
+        Token::Let,
+        Token::Ident("x".into()),
+        Token::Assign,
+        Token::Int(10),
+        Token::Semicolon,
+        Token::Ident("x".into()),
+        Token::Slash,
+        Token::Int(2),
+        Token::Semicolon,
+        Token::EOF,
+    ];
+
+    for token in expected {
+        assert_eq!(lexer.next_token(), token);
+    }
+    assert!(lexer.errors().is_empty());
+}
+
+#[test]
+fn test_doc_comments_attach_to_next_token() {
+    let input = "
+        /// Adds two numbers.
+        ///Second line.
+        //// not a doc comment
+        let add = 1;
+    ";
+
+    let mut lexer = Lexer::new(input);
+
+    let tok = lexer.next_token();
+    assert_eq!(tok, Token::Let);
+    assert_eq!(tok.docs, vec!["Adds two numbers.", "Second line."]);
+    assert!(lexer.next_token().docs.is_empty());
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let mut lexer = Lexer::new("1 /* open /* inner */");
+
+    assert_eq!(lexer.next_token(), Token::Int(1));
+    assert_eq!(lexer.next_token(), Token::EOF);
+    assert_eq!(lexer.errors()[0].message, "unterminated block comment");
+    assert_eq!(lexer.errors()[0].span.column, 3);
+}
+
+#[test]
+fn test_numeric_literals() {
+    let input = "0xFF 0b1010 0o17 1_000_000 2.75 1e-9 2.5E+3 7";
+
+    let mut lexer = Lexer::new(input);
+
+    assert_eq!(lexer.next_token(), Token::Int(255));
+    assert_eq!(lexer.next_token(), Token::Int(10));
+    assert_eq!(lexer.next_token(), Token::Int(15));
+    assert_eq!(lexer.next_token(), Token::Int(1_000_000));
+    assert_eq!(lexer.next_token(), Token::Float(2.75));
+    assert_eq!(lexer.next_token(), Token::Float(1e-9));
+    assert_eq!(lexer.next_token(), Token::Float(2500.0));
+    assert_eq!(lexer.next_token(), Token::Int(7));
+    assert_eq!(lexer.next_token(), Token::EOF);
+    assert!(lexer.errors().is_empty());
+}
+
+#[test]
+fn test_malformed_numbers_are_diagnosed() {
+    let cases = vec![
+        ("99999999999999999999", "integer literal out of range", 1),
+        ("0xFFFFFFFFFFFFFFFFF", "integer literal out of range", 1),
+        ("1e999", "float literal out of range", 1),
+        ("0b102", "invalid character '2' in number literal", 1),
+        ("12abc", "invalid character 'a' in number literal", 1),
+        ("0x", "missing digits after number prefix", 1),
+        ("1__000", "misplaced digit separator '_'", 3),
+        ("1000_", "trailing digit separator '_'", 1),
+    ];
+
+    for (input, message, column) in cases {
+        let mut lexer = Lexer::new(input);
+        lexer.next_token();
+        assert_eq!(lexer.errors().len(), 1, "input: {}", input);
+        assert_eq!(lexer.errors()[0].message, message, "input: {}", input);
+        assert_eq!(lexer.errors()[0].span.column, column, "input: {}", input);
+    }
+}
+
+#[test]
+fn test_operator_tokens() {
+    let input = "<= >= % ** & | ^ << >> && || += -= *= /= < > * /";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected = vec![
+        Token::LessEqual,
+        Token::GreaterEqual,
+        Token::Percent,
+        Token::Power,
+        Token::Ampersand,
+        Token::Pipe,
+        Token::Caret,
+        Token::ShiftLeft,
+        Token::ShiftRight,
+        Token::And,
+        Token::Or,
+        Token::PlusAssign,
+        Token::MinusAssign,
+        Token::AsteriskAssign,
+        Token::SlashAssign,
+        Token::LessThan,
+        Token::GreaterThan,
+        Token::Asterisk,
+        Token::Slash,
+        Token::EOF,
+    ];
+
+    for token in expected {
+        assert_eq!(lexer.next_token(), token);
+    }
+}
+
+#[test]
+fn test_interpolated_string_tokens() {
+    let input = r#""total: ${a + {b}} // ${"x${y}"}!" "\${not}""#;
+
+    let mut lexer = Lexer::new(input);
+
+    let expected = vec![
+        Token::TemplateStart,
+        Token::StringFragment("total: ".into()),
+        Token::InterpolationStart,
+        Token::Ident("a".into()),
+        Token::Plus,
+        Token::LBrace,
+        Token::Ident("b".into()),
+        Token::RBrace,
+        Token::InterpolationEnd,
+        Token::StringFragment(" // ".into()),
+        Token::InterpolationStart,
+        Token::TemplateStart,
+        Token::StringFragment("x".into()),
+        Token::InterpolationStart,
+        Token::Ident("y".into()),
+        Token::InterpolationEnd,
+        Token::TemplateEnd,
+        Token::InterpolationEnd,
+        Token::StringFragment("!".into()),
+        Token::TemplateEnd,
+        Token::String("${not}".into()),
+        Token::EOF,
+    ];
+
+    for token in expected {
+        assert_eq!(lexer.next_token(), token);
+    }
+    assert!(lexer.errors().is_empty());
+}
+
+#[test]
+fn test_unterminated_interpolation() {
+    let mut lexer = Lexer::new("\"a ${b");
+
+    while lexer.next_token() != Token::EOF {}
+
+    assert_eq!(lexer.errors().len(), 1);
+    assert_eq!(lexer.errors()[0].message, "unterminated string interpolation");
+    assert_eq!(lexer.errors()[0].span.column, 1);
+}
+
+#[test]
+fn test_tokens_borrow_from_source() {
+    use std::borrow::Cow;
+
+    let input = r#"name "plain" "esc\n""#;
+    let mut lexer = Lexer::new(input);
+
+    match lexer.next_token().token {
+        Token::Ident(Cow::Borrowed(name)) => {
+            assert_eq!(name, "name");
+            assert_eq!(name.as_ptr(), input.as_ptr());
+        }
+        other => panic!("expected borrowed identifier, found {:?}", other),
+    }
+    assert!(matches!(lexer.next_token().token, Token::String(Cow::Borrowed("plain"))));
+    assert!(matches!(lexer.next_token().token, Token::String(Cow::Owned(_))));
+}
+
+#[test]
+fn test_lexer_is_an_iterator() {
+    let tokens: Vec<Token> = Lexer::new("let x = 1;").map(|tok| tok.token).collect();
+
+    assert_eq!(
+        tokens,
+        vec![Token::Let, Token::Ident("x".into()), Token::Assign, Token::Int(1), Token::Semicolon]
+    );
+}
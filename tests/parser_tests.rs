@@ -1,6 +1,6 @@
-use aion::lexer::Lexer;
-use aion::parser::Parser;
-use aion::ast::{Statement};
+use aion::lexer::{Lexer, Span, Token};
+use aion::parser::{Parser, Precedence};
+use aion::ast::{Expression, Pattern, Statement, TypeAnnotation, TypeExpr};
 
 #[test]
 fn test_let_statements() {
@@ -26,3 +26,439 @@ fn test_let_statements() {
         _ => panic!("expected let statement"),
     }
 }
+
+#[test]
+fn test_parse_errors_are_collected() {
+    let input = "
+        let = 5;
+        let x 10;
+        let y = (1 + 2;
+        let z = 3;
+        z * ;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    // Only the one good statement survives, and every typo is reported.
+    assert_eq!(program.len(), 1);
+    match &program[0] {
+        Statement::LetStatement { name, .. } => assert_eq!(name, "z"),
+        _ => panic!("expected let statement"),
+    }
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "expected identifier, found '=' at 2:13",
+            "expected '=', found '10' at 3:15",
+            "expected ')', found ';' at 4:23",
+            "expected expression, found ';' at 6:13",
+        ]
+    );
+
+    let err = &parser.errors()[1];
+    assert_eq!(err.expected, vec!["'='"]);
+    assert_eq!(err.found, Token::Int(10));
+}
+
+#[test]
+fn test_illegal_characters_do_not_hang_the_parser() {
+    let mut parser = Parser::new(Lexer::new("let a = 1 @ 2; let b = 3;"));
+    let program = parser.parse_program();
+
+    assert_eq!(parser.lexer_errors()[0].message, "unexpected character '@'");
+    assert_eq!(program.len(), 2);
+}
+
+#[test]
+fn test_equality_operators_parse_as_infix() {
+    let mut parser = Parser::new(Lexer::new("5 == 5; 5 != 3 == 1 < 2;"));
+    let program = parser.parse_program();
+
+    assert!(parser.errors().is_empty());
+    assert_eq!(program.len(), 2);
+    match &program[0] {
+        Statement::ExpressionStatement(Expression::Infix { operator, .. }) => assert_eq!(operator, "=="),
+        other => panic!("expected infix expression, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_extensions_can_register_parse_functions() {
+    let mut parser = Parser::new(Lexer::new("2 ^ 3 ^ 2;"));
+
+    // Re-purpose `^` as a right-associative power operator.
+    parser.register_infix(Token::Caret, Precedence::POWER, |p, left| {
+        let span = p.current_token().span;
+        p.advance_tokens();
+        let right = p.parse_expression(Precedence::PRODUCT)?;
+        Some(Expression::Infix {
+            left: Box::new(left),
+            operator: "**".to_string(),
+            right: Box::new(right),
+            span,
+        })
+    });
+
+    let program = parser.parse_program();
+
+    let power = |left, right, column| Expression::Infix {
+        left: Box::new(left),
+        operator: "**".to_string(),
+        right: Box::new(right),
+        span: Span { start: column - 1, end: column, line: 1, column },
+    };
+    assert_eq!(
+        program,
+        vec![Statement::ExpressionStatement(power(
+            Expression::NumberLiteral(2),
+            power(Expression::NumberLiteral(3), Expression::NumberLiteral(2), 7),
+            3,
+        ))]
+    );
+}
+
+#[test]
+fn test_block_recovers_inside_braces() {
+    let input = "
+        if x { let = 1; y } else { z }
+        { let a = ; b }
+        done;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    assert_eq!(parser.errors().len(), 2);
+    assert_eq!(program.len(), 3);
+    match &program[1] {
+        Statement::Block(statements) => assert!(matches!(
+            statements.as_slice(),
+            [Statement::ExpressionStatement(Expression::Identifier { name, .. })] if name == "b"
+        )),
+        other => panic!("expected block statement, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_break_and_continue_outside_a_loop_are_errors() {
+    let input = "
+        break;
+        while x { continue; fn() { break 1; }; break x }
+        continue;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "'break' outside of a loop at 2:9",
+            "'break' outside of a loop at 3:36",
+            "'continue' outside of a loop at 4:9",
+        ]
+    );
+}
+
+#[test]
+fn test_assignment_is_right_associative_and_checks_its_target() {
+    let mut parser = Parser::new(Lexer::new("a = b = 1; 1 = 2; const c = 3;"));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages, vec!["invalid assignment target for '=' at 1:14"]);
+
+    match &program[0] {
+        Statement::ExpressionStatement(Expression::Assign { target, value, .. }) => {
+            assert!(matches!(&**target, Expression::Identifier { name, span } if name == "a" && span.to_string() == "1:1"));
+            assert!(matches!(**value, Expression::Assign { .. }));
+        }
+        other => panic!("expected assignment, found {:?}", other),
+    }
+    assert!(matches!(
+        &program[1],
+        Statement::ConstStatement { name, annotation: None, value: Expression::NumberLiteral(3), span }
+            if name == "c" && span.to_string() == "1:19"
+    ));
+}
+
+#[test]
+fn test_index_binds_like_a_call() {
+    let mut parser = Parser::new(Lexer::new("-a[0]; a[1:];"));
+    let program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+
+    match &program[0] {
+        Statement::ExpressionStatement(Expression::Prefix { operator, right, .. }) => {
+            assert_eq!(operator, "-");
+            assert!(matches!(**right, Expression::Index { .. }));
+        }
+        other => panic!("expected prefix expression, found {:?}", other),
+    }
+    match &program[1] {
+        Statement::ExpressionStatement(Expression::Slice { start, end, .. }) => {
+            assert_eq!(start.as_deref(), Some(&Expression::NumberLiteral(1)));
+            assert_eq!(*end, None);
+        }
+        other => panic!("expected slice, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_block_like_statements_end_at_their_brace() {
+    let mut parser = Parser::new(Lexer::new("if a { b } [1, 2]; let h = {\"k\": 1};"));
+    let program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+
+    assert_eq!(program.len(), 3);
+    assert!(matches!(program[0], Statement::ExpressionStatement(Expression::If { .. })));
+    assert!(matches!(program[1], Statement::ExpressionStatement(Expression::ArrayLiteral(_))));
+    assert!(matches!(
+        program[2],
+        Statement::LetStatement { value: Expression::HashLiteral(_), .. }
+    ));
+}
+
+#[test]
+fn test_pattern_errors() {
+    let input = "
+        match x { [..a, ..b] => 1 }
+        match x { a + 1 => 2 }
+        let [_x, ..] = y;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "only one '..' is allowed in an array pattern at 2:25",
+            "expected '=>', found '+' at 3:21",
+        ]
+    );
+    assert!(matches!(program.last(), Some(Statement::LetPattern { .. })));
+}
+
+#[test]
+fn test_struct_declarations_and_literals() {
+    let input = "
+        struct Point { x, y, x }
+        let p = Point { x: 1, y };
+        while p.x { }
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages, vec!["field 'x' is declared twice at 2:30"]);
+
+    // The bad declaration is dropped.
+    assert_eq!(program.len(), 2);
+    match &program[0] {
+        Statement::LetStatement { value: Expression::StructLiteral { name, fields, .. }, .. } => {
+            assert_eq!(name, "Point");
+            // The shorthand `y` reads the variable `y`, found at the field name.
+            match &fields[1] {
+                (field, Expression::Identifier { name, span }) => {
+                    assert_eq!((field.as_str(), name.as_str(), span.to_string()), ("y", "y", "3:31".to_string()));
+                }
+                other => panic!("expected shorthand field, found {:?}", other),
+            }
+        }
+        other => panic!("expected struct literal, found {:?}", other),
+    }
+    assert!(matches!(
+        &program[1],
+        Statement::ExpressionStatement(Expression::While { condition, .. })
+            if matches!(**condition, Expression::Field { .. })
+    ));
+}
+
+#[test]
+fn test_enum_declarations_and_paths() {
+    let input = "
+        enum Shape { Circle(r), Empty, Circle }
+        enum Shape { Circle(r), Rect(w, h), Empty }
+        let Shape::Rect(w, _) = Shape::Rect(1, 2);
+        let Some(x) = y;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages, vec!["variant 'Circle' is declared twice at 2:40"]);
+
+    assert_eq!(program.len(), 3);
+    match &program[0] {
+        Statement::EnumDeclaration { name, variants } => {
+            assert_eq!(name, "Shape");
+            assert_eq!(variants[1].fields, vec!["w".to_string(), "h".to_string()]);
+            assert!(variants[2].fields.is_empty());
+        }
+        other => panic!("expected enum declaration, found {:?}", other),
+    }
+    match &program[1] {
+        Statement::LetPattern { pattern: Pattern::Variant { enum_name, variant, fields, .. }, value, .. } => {
+            assert_eq!(enum_name.as_deref(), Some("Shape"));
+            assert_eq!(variant, "Rect");
+            assert_eq!(fields[1], Pattern::Wildcard);
+            assert!(matches!(value, Expression::Call { function, .. } if matches!(**function, Expression::Path { .. })));
+        }
+        other => panic!("expected variant pattern, found {:?}", other),
+    }
+    assert!(matches!(
+        &program[2],
+        Statement::LetPattern { pattern: Pattern::Variant { enum_name: None, .. }, .. }
+    ));
+}
+
+#[test]
+fn test_imports_and_exports_parse_at_top_level_only() {
+    let input = "
+        import \"lib/math.aion\" as math;
+        export const limit = 10;
+        export 5;
+        let f = fn() { export let x = 1; };
+        if true { import \"a.aion\" as a; }
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "expected 'let' or 'const' or 'fn' or 'struct' or 'enum' or 'trait', found '5' at 4:16",
+            "'export' is only allowed at the top level at 5:24",
+            "'import' is only allowed at the top level at 6:19",
+        ]
+    );
+
+    match &program[0] {
+        Statement::Import { path, alias, .. } => {
+            assert_eq!(path, "lib/math.aion");
+            assert_eq!(alias, "math");
+        }
+        other => panic!("expected import, found {:?}", other),
+    }
+    assert!(matches!(
+        &program[1],
+        Statement::Export(declaration) if matches!(**declaration, Statement::ConstStatement { .. })
+    ));
+}
+
+#[test]
+fn test_try_needs_catch_or_finally() {
+    let input = "
+        try { a } catch { b }
+        try { a }
+        try { a } catch e { b } finally { c } [1];
+        throw e;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages, vec!["expected 'catch' or 'finally', found 'try' at 4:9"]);
+
+    match &program[0] {
+        Statement::ExpressionStatement(Expression::Try { catch: Some(catch), finally: None, .. }) => {
+            assert_eq!(catch.name, None);
+        }
+        other => panic!("expected try expression, found {:?}", other),
+    }
+    // Like `if`, a try statement ends at its last '}'.
+    match &program[1] {
+        Statement::ExpressionStatement(Expression::Try { catch: Some(catch), finally: Some(_), .. }) => {
+            assert_eq!(catch.name.as_deref(), Some("e"));
+        }
+        other => panic!("expected try expression, found {:?}", other),
+    }
+    assert!(matches!(program[2], Statement::ExpressionStatement(Expression::ArrayLiteral(_))));
+    assert!(matches!(program[3], Statement::Throw { .. }));
+}
+
+#[test]
+fn test_type_annotations() {
+    let input = "
+        let xs: [int] = [];
+        fn add(a: int, b) -> {string: fn(int) -> int} { a }
+        let y: = 1;
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages, vec!["expected type, found '=' at 4:16"]);
+
+    match &program[0] {
+        Statement::LetStatement { annotation: Some(TypeAnnotation { ty, span }), .. } => {
+            assert_eq!(*ty, TypeExpr::Array(Box::new(TypeExpr::Named("int".to_string()))));
+            assert_eq!(span.to_string(), "2:17");
+        }
+        other => panic!("expected annotated let, found {:?}", other),
+    }
+    // `fn name(...)` is a let bound to a function literal.
+    match &program[1] {
+        Statement::LetStatement { name, annotation: None, value: Expression::FunctionLiteral { parameter_types, return_type, .. }, .. } => {
+            assert_eq!(name, "add");
+            assert_eq!(parameter_types[0].as_ref().map(|a| a.ty.to_string()).as_deref(), Some("int"));
+            assert!(parameter_types[1].is_none());
+            assert_eq!(return_type.as_ref().unwrap().ty.to_string(), "{string: fn(int) -> int}");
+        }
+        other => panic!("expected function declaration, found {:?}", other),
+    }
+}
+
+#[test]
+fn test_traits_and_type_parameters() {
+    let input = "
+        trait Show { fn show(self); fn twice<T: Show + Eq>(self, other: T) -> string { self.show() } }
+        impl Show for Point { fn show(self) { \"p\" } }
+        fn describe<T: Show>(value: T) { value.show() }
+        fn broken<T, T>(a: T) { a }
+    ";
+
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+
+    let messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    assert_eq!(messages, vec!["type parameter 'T' is declared twice at 5:22"]);
+
+    match &program[0] {
+        Statement::Trait { name, methods, .. } => {
+            assert_eq!(name, "Show");
+            assert_eq!(methods[0].name, "show");
+            assert!(methods[0].default.is_none());
+            assert_eq!(methods[1].type_parameters[0].to_string(), "T: Show + Eq");
+            assert_eq!(methods[1].parameters, vec!["self", "other"]);
+            assert!(methods[1].default.is_some());
+        }
+        other => panic!("expected trait, found {:?}", other),
+    }
+    match &program[1] {
+        Statement::Impl { type_name, trait_name, .. } => {
+            assert_eq!(type_name, "Point");
+            assert_eq!(trait_name.as_deref(), Some("Show"));
+        }
+        other => panic!("expected impl, found {:?}", other),
+    }
+    match &program[2] {
+        Statement::LetStatement { value: Expression::FunctionLiteral { type_parameters, parameter_types, .. }, .. } => {
+            assert_eq!(type_parameters[0].to_string(), "T: Show");
+            assert_eq!(parameter_types[0].as_ref().map(|a| a.ty.to_string()).as_deref(), Some("T"));
+        }
+        other => panic!("expected generic function, found {:?}", other),
+    }
+}